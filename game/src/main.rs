@@ -2,432 +2,202 @@
 Также же в Cargo.toml
 
 [dependencies]
-rand="*"
 three="*"
-serde="*"
-bincode="*"
-serde_derive="*"
+snake_core={ path = "../snake_core" }
 
 прописываем
 */
-extern crate rand;
 extern crate three;
-extern crate bincode;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
+extern crate snake_core;
 
 // Добавляем нужные нам вещи в нашу область видимости.
-use rand::Rng;
-use three::*;
+use std::cell::RefCell;
 use std::error::Error;
+use std::rc::Rc;
+use three::*;
+use snake_core::{option, parse, parse_option, phase_message, player_name, run, AiLevel, AnimationConfig, AnimationRepository, Command, Difficulty, Direction, FoodConfig, Game, GameController, GameSnapshot, GameSource, InputSource, Level, LevelRepository, Phase, PictureRepository, PlaybackCommand, PointDtoType, RemoteGame, Renderer, Replay, ReplayPlayer, ReplayRepository, Rgb, SaveRepository, ScoreRepository, Theme, Topology, DEFAULT_PORT};
 
-//Entities ------------------------------------------------------------------
-
-/*
-Это макросы. Они генерируют какой нибудь код автоматически.
-В нашем конкретном случае:
-Debug - Создаст код который позволить выводить нашу структуру в терминал
-Clone - Создаст код который будет копировать нашу структуру т. е. у нашей структуры появиться метод clone()
-Eq и PartialEq позволять сравнивать наши Point с помошью оператора ==
-*/
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-//Обьявление структуры с двумя полями. Она будет играть роль точки
-struct Point {
-    x: u8,
-    y: u8,
-}
+//Файл в который сохраняется запись последней законченной игры
+const LAST_REPLAY: &str = "./last.replay";
 
-//Методы нашей структуры
-impl Point {
-    // Можно было использовать просто оператор == В общем, это метот который проверяет пересекаються ли наши точки
-    pub fn intersects(&self, point: &Point) -> bool {
-        self.x == point.x && self.y == point.y
-    }
-}
+//Файл в который сохраняется недоигранная игра при выходе
+const SAVE_FILE: &str = "./save.data";
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-//Эта структура будет хранить обьектное представление границ фрейма в пределах которого будет двигаться наша змейка
-struct Frame {
-    min_x: u8,
-    min_y: u8,
-    max_x: u8,
-    max_y: u8,
-}
+//Размер клетки поля на картинке кадра в точках
+const SCREENSHOT_CELL: u32 = 16;
 
-impl Frame {
-    pub fn intersects(&self, point: &Point) -> bool {
-        point.x == self.min_x
-            || point.y == self.min_y
-            || point.x == self.max_x
-            || point.y == self.max_y
-    }
-}
+//На сколько шагов перематывается повтор стрелками влево и вправо
+const SEEK_TICKS: u64 = 50;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-//Обьявление перечисления с 4 вариантами
-//Оно будет отвечать за то куда в данный момент повернута голова змейки
-enum Direction {
-    Left,
-    Right,
-    Top,
-    Bottom,
-}
-
-//Резализация трейта (в других языках это еще называеться интерфейс)
-// для нашего перечесления.
-//Обьект реализующий этот трейт способен иметь значение по умолчанию.
-impl Default for Direction {
-    fn default() -> Direction {
-        return Direction::Right;
-    }
+//------------------------View ---------------
+//Команды окна: команды игры и действия самого представления о которых игра не знает
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Game(Command),
+    //Продолжить игру сохраненную при прошлом выходе
+    Resume,
+    //Сохранить картинку текущего кадра
+    Screenshot,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-//Собственно наша змейка
-struct Snake {
-    direction: Direction,
-    points: std::collections::VecDeque<Point>,
-    start_x: u8,
-    start_y: u8,
+//Откуда представление берет игру: из своего контроллера, с сервера по сети или из записи
+enum Source {
+    //Локальная игра и недоигранная игра с прошлого запуска вместе с ее записью.
+    //Ее можно продолжить с заставки
+    Local(GameController, Option<(Game, Option<Replay>)>),
+    Remote(RemoteGame),
+    Replay(ReplayPlayer),
 }
 
-impl Snake {
-    //Статический метод конструктор для инициализации нового экземлпяра нашей змейки
-    pub fn new(x: u8, y: u8) -> Snake {
-        let mut points = std::collections::VecDeque::new();
-        for i in 0..3 {
-            points.push_front(Point { x: x + i, y: i + y });
+impl GameSource<Action> for Source {
+    //Передаем команды игре. Локальная игра двигается сама, сетевую двигает сервер
+    fn update(self, time_delta: f32, actions: Vec<Action>) -> Source {
+        //Картинку кадра можно сохранить в любой игре, в том числе в сетевой и в повторе
+        if actions.contains(&Action::Screenshot) {
+            screenshot(&self.snapshot());
         }
-        Snake { direction: Direction::default(), points, start_x: x, start_y: y }
-    }
-    //Увеличивает длину нашей змейки на одну точку
-    pub fn grow(self) -> Snake {
-        let mut points = self.points;
-        if let Some(tail) = points.pop_back() {
-            points.push_back(Point { x: tail.x, y: tail.y });
-            points.push_back(tail);
-        }
-        Snake { points, ..self }
-    }
-
-    //Сбрасывает нашу змейку в начальное состояние
-    pub fn reset(self) -> Snake {
-        Snake::new(self.start_x, self.start_y)
-    }
-
-    //Поворачивает голову змейки в нужном нам направлении
-    pub fn turn(self, direction: Direction) -> Snake {
-        Snake { direction, ..self }
-    }
-
-    //Если голова змейки достает до еды то увеличивает длину змейки на один и возврашает информацию о том была ли еда съедена
-    pub fn try_eat(self, point: &Point) -> (Snake, bool) {
-        let head = self.head();
-        if head.intersects(point) {
-            return (self.grow(), true);
-        }
-        (self, false)
-    }
-
-    //Если голова змейки столкнулась с фреймом то возвращает змейку в начальное состояние
-    pub fn try_intersect_frame(self, frame: &Frame) -> Snake {
-        let head = self.head();
-        if frame.intersects(&head) {
-            return self.reset();
-        }
-        self
-    }
-
-    //Если голова змейки столкнулась с остальной частью то возвращает змейку в начальное состояние.
-    pub fn try_intersect_tail(self) -> Snake {
-        let head = self.head();
-        let p = self.points.clone();
-        let points = p.into_iter().filter(|p| head.intersects(p));
-        if points.count() > 1 {
-            return self.reset();
-        }
-        self
-    }
-
-    //Дает голову змейки
-    pub fn head(&self) -> Point {
-        self.points.front().unwrap().clone()
-    }
-
-    //Перемещает змейку на одну точку в том направление куда в данный момент смотрит голова змейки
-    pub fn move_snake(self) -> Snake {
-        let mut points = self.points.clone();
-        if let Some(mut tail) = points.pop_back() {
-            let head = self.head();
-            match self.direction {
-                Direction::Right => {
-                    tail.x = head.x + 1;
-                    tail.y = head.y;
-                }
-                Direction::Left => {
-                    tail.x = head.x - 1;
-                    tail.y = head.y;
-                }
-                Direction::Top => {
-                    tail.x = head.x;
-                    tail.y = head.y - 1;
-                }
-                Direction::Bottom => {
-                    tail.x = head.x;
-                    tail.y = head.y + 1;
+        let resume = actions.contains(&Action::Resume);
+        let commands: Vec<Command> = actions.into_iter()
+            .filter_map(|a| match a {
+                Action::Game(command) => Some(command),
+                _ => None,
+            })
+            .collect();
+        match self {
+            Source::Local(controller, saved) => {
+                let title = controller.get_phase() == Phase::Title;
+                let was_over = matches!(controller.get_phase(), Phase::GameOver(_));
+                //Продолжить можно только с заставки
+                let (controller, saved) = match saved {
+                    Some((game, recording)) if title && resume => (GameController::resumed(game, recording), None),
+                    saved => (controller, saved),
+                };
+                let controller = controller.update(time_delta, commands);
+                //Сохранение больше не нужно когда его продолжили или начали новую игру
+                let saved = if title && controller.get_phase() != Phase::Title {
+                    let _ = SaveRepository::remove(SAVE_FILE);
+                    None
+                } else {
+                    saved
+                };
+                //Законченную игру сразу записываем в файл чтобы ее можно было посмотреть еще раз,
+                //и запоминаем рекорд
+                if let (false, Phase::GameOver(_)) = (was_over, controller.get_phase()) {
+                    if let Some(replay) = controller.get_replay() {
+                        if let Err(e) = ReplayRepository::save(LAST_REPLAY, replay) {
+                            eprintln!("Can not save replay: {}", e);
+                        }
+                    }
+                    save_max_score(&controller);
                 }
+                Source::Local(controller, saved)
             }
-            points.push_front(tail);
-        }
-        Snake { points, ..self }
-    }
-}
-
-//Data Access Layer ----------------------------------------------------------------
-
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-//Структура для создания новой еды для змейки
-struct FoodGenerator {
-    frame: Frame
-}
-
-impl FoodGenerator {
-    //Создает новую точку в случайном месте в пределах фрейма
-    pub fn generate(&self) -> Point {
-        let x = rand::thread_rng().gen_range(self.frame.min_x + 1, self.frame.max_x);
-        let y = rand::thread_rng().gen_range(self.frame.min_y + 1, self.frame.max_y);
-        Point { x, y }
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-//Хранит текущий и максимальный счет игры
-struct ScoreRepository {
-    score: usize
-}
-
-impl ScoreRepository {
-    //Статический метод для сохранения текущего счета в файле
-    // Result это перечесление которое может хранить в себе либо ошибку либо результат вычислений
-    fn save(value: usize) -> Result<(), Box<Error>> {
-        use std::fs::File;
-        use std::io::Write;
-        let score = ScoreRepository { score: value };
-        //Сериализуем структуру в массив байтов с помощью библиотеки bincode
-        let bytes: Vec<u8> = bincode::serialize(&score)?;
-        //Создаем новый файл или если он уже сушествует то перезаписываем его.
-        let mut file = File::create("./score.data")?;
-        match file.write_all(&bytes) {
-            Ok(t) => Ok(t),
-            //Error это трейт а у трейт нет точного размера во время компиляции поэтому
-            // нам надо обернуть значнеие в Box и в результате мы работает с указателем на
-            //кучу в памяти где лежит наш обьект а не с самим обьектом а у указателя есть определенный размер
-            // известный во время компиляции
-            Err(e) => Err(Box::new(e))
-        }
-    }
-
-    //Загружаем сохраненный результат из файла
-    fn load() -> Result<usize, Box<Error>> {
-        use std::fs::File;
-        let file = File::open("./score.data")?;
-        let data: ScoreRepository = bincode::deserialize_from(file)?;
-        Ok(data.score)
-    }
-}
-
-//Business Logic Layer------------------------------------------------------------
-
-#[derive(Debug, Clone, Default)]
-//Обьектное представление логики нашей игры
-struct Game {
-    snake: Snake,
-    frame: Frame,
-    food: Point,
-    food_generator: FoodGenerator,
-    score: usize,
-    max_score: usize,
-    total_time: f32,
-}
-
-impl Game {
-    //Конструктор для создания игры с фреймом заданной высоты и ширины
-    fn new(height: u8, width: u8) -> Game {
-        let frame = Frame { min_x: 0, min_y: 0, max_x: width, max_y: height };
-        let generator = FoodGenerator { frame: frame.clone() };
-        let food = generator.generate();
-        let snake = Snake::new(width / 2, height / 2);
-        Game {
-            snake,
-            frame,
-            food,
-            food_generator: generator,
-            score: 0,
-            max_score: match ScoreRepository::load() {
-                Ok(v) => v,
-                Err(_) => 0
-            },
-            total_time: 0f32,
-        }
-    }
-    // Проверяем, прошло ли достаточно времени с момента когда мы в последний раз
-    //двигали нашу змейку и если да то передвигаем ее
-    // и проверяем столкновение головы змейки с остальными обьектами игры
-    // иначе ничего не делаем
-    fn update(self, time_delta_in_seconds: f32) -> Game {
-        let (game, is_moving) = self.is_time_to_move(time_delta_in_seconds);
-        if is_moving {
-            Game {
-                snake: game.snake
-                    .move_snake()
-                    .try_intersect_tail()
-                    .try_intersect_frame(&game.frame),
-                ..game
+            Source::Remote(remote) => Source::Remote(GameSource::update(remote, time_delta, commands)),
+            Source::Replay(player) => {
+                let tick = player.tick();
+                //Во время повтора стрелки перематывают и меняют скорость
+                let commands = commands.into_iter().filter_map(|c| match c {
+                    Command::Pause | Command::Confirm => Some(PlaybackCommand::Pause),
+                    Command::Step => Some(PlaybackCommand::Seek(tick + 1)),
+                    Command::Turn(0, Direction::Right) => Some(PlaybackCommand::Seek(tick + SEEK_TICKS)),
+                    Command::Turn(0, Direction::Left) => Some(PlaybackCommand::Seek(tick.saturating_sub(SEEK_TICKS))),
+                    Command::Turn(0, Direction::Bottom) => Some(PlaybackCommand::Faster),
+                    Command::Turn(0, Direction::Top) => Some(PlaybackCommand::Slower),
+                    _ => None,
+                }).collect();
+                Source::Replay(player.update(time_delta, commands))
             }
-                .try_eat()
-        } else {
-            game
         }
     }
 
-    //Проверяем, настало ли время для того чтобы передвинуть змейку.
-    fn is_time_to_move(self, time_delta_in_seconds: f32) -> (Game, bool) {
-        let time_to_move: f32 = 0.030;
-        let mut game = self;
-        game.total_time += time_delta_in_seconds;
-        if game.total_time > time_to_move {
-            game.total_time -= time_to_move;
-            (game, true)
-        } else {
-            (game, false)
+    fn snapshot(&self) -> GameSnapshot {
+        match self {
+            Source::Local(controller, _) => controller.snapshot(),
+            Source::Remote(remote) => GameSource::snapshot(remote),
+            Source::Replay(player) => player.controller.snapshot(),
         }
     }
 
-    //Проверяем, сьела ли наша змейку еду и если да
-    // то создаем новую еду, начисляем игроку очки
-    // иначе сбрасываем игроку текуший счет
-    fn try_eat(self) -> Game {
-        let mut game = self;
-        let initial_snake_len = 3;
-        if game.snake.points.len() == initial_snake_len {
-            game.score = 0
+    //Пауза, автопилот игрока за этим компьютером, сетевое подключение или шаг повтора
+    fn status(&self) -> String {
+        match self {
+            Source::Replay(replay) => format!(
+                "REPLAY {}/{} X{}{} (P - PAUSE, LEFT/RIGHT - SEEK, UP/DOWN - SPEED)",
+                replay.tick(), replay.replay.ticks, replay.speed(), if replay.is_paused() { " PAUSED" } else { "" }),
+            Source::Remote(remote) if !remote.is_connected() => remote.status(),
+            Source::Local(controller, _) if controller.is_autopilot(0) => "AUTOPILOT (H - TAKE CONTROL)".to_string(),
+            Source::Local(controller, Some(_)) if controller.get_phase() == Phase::Title => "PRESS R TO RESUME".to_string(),
+            Source::Local(_, _) => String::new(),
+            Source::Remote(remote) => match remote.player() {
+                Some(p) if remote.snapshot().autopilots.get(p).cloned().unwrap_or(false) => "AUTOPILOT (H - TAKE CONTROL)".to_string(),
+                _ => remote.status(),
+            },
         }
-        let (snake, eaten) = game.snake.clone().try_eat(&game.food);
-        game.snake = snake;
-        if eaten {
-            game.food = game.food_generator.generate();
-            game.score += 1;
-            if game.max_score < game.score {
-                game.max_score = game.score;
-                ScoreRepository::save(game.max_score);
-            }
-        };
-        game
-    }
-
-    // Поворачиваем змейку в нужном направлении
-    fn handle_input(self, input: Direction) -> Game {
-        let snake = self.snake.turn(input);
-        Game { snake, ..self }
     }
 }
 
-//Application Layer--------------------------------------------------------------
-// --- Model ----
-#[derive(Debug, Clone, Eq, PartialEq)]
-enum PointDtoType {
-    Head,
-    Tail,
-    Food,
-    Frame,
+//Клавиатура окна three. Окно общее с ThreeRenderer
+struct ThreeInput {
+    window: Rc<RefCell<three::Window>>,
 }
 
-impl Default for PointDtoType {
-    fn default() -> PointDtoType {
-        PointDtoType::Frame
-    }
-}
-
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-//Модель котору будет видеть представление для отображения пользователю.
-struct PointDto {
-    x: u8,
-    y: u8,
-    state_type: PointDtoType,
-}
-
-//------------------------------Controller -----------------------------
-#[derive(Debug, Clone, Default)]
-// Контроллер который будет посредником между представлением и логикой нашей игры
-struct GameController {
-    game: Game,
-}
-
-impl GameController {
-    fn new() -> GameController {
-        GameController { game: Game::new(30, 30) }
-    }
-
-    //Получить коллекцию точек которые нужно отрисовать в данный момент
-    fn get_state(&self) -> Vec<PointDto> {
-        let mut vec: Vec<PointDto> = Vec::new();
-        vec.push(PointDto { x: self.game.food.x, y: self.game.food.y, state_type: PointDtoType::Food });
-        let head = self.game.snake.head();
-        vec.push(PointDto { x: head.x, y: head.y, state_type: PointDtoType::Head });
-        //Все точки за исключением головы змеи
-        for p in self.game.snake.points.iter().filter(|p| **p != head) {
-            vec.push(PointDto { x: p.x, y: p.y, state_type: PointDtoType::Tail });
-        }
-        //горизонтальные линии фрейма
-        for x in self.game.frame.min_x..=self.game.frame.max_x {
-            vec.push(PointDto { x: x, y: self.game.frame.max_y, state_type: PointDtoType::Frame });
-            vec.push(PointDto { x: x, y: self.game.frame.min_y, state_type: PointDtoType::Frame });
-        }
-        //Вериткальные линии фрейма
-        for y in self.game.frame.min_y..=self.game.frame.max_y {
-            vec.push(PointDto { x: self.game.frame.max_x, y: y, state_type: PointDtoType::Frame });
-            vec.push(PointDto { x: self.game.frame.min_x, y: y, state_type: PointDtoType::Frame });
+impl ThreeInput {
+    //Команда игры для клавиши.
+    //Первый игрок управляет стрелками, второй клавишами WASD
+    fn command(key: three::Key) -> Option<Command> {
+        match key {
+            three::Key::Left => Some(Command::Turn(0, Direction::Left)),
+            three::Key::Right => Some(Command::Turn(0, Direction::Right)),
+            three::Key::Down => Some(Command::Turn(0, Direction::Top)),
+            three::Key::Up => Some(Command::Turn(0, Direction::Bottom)),
+            three::Key::A => Some(Command::Turn(1, Direction::Left)),
+            three::Key::D => Some(Command::Turn(1, Direction::Right)),
+            three::Key::S => Some(Command::Turn(1, Direction::Top)),
+            three::Key::W => Some(Command::Turn(1, Direction::Bottom)),
+            three::Key::Return | three::Key::Space => Some(Command::Confirm),
+            three::Key::P => Some(Command::Pause),
+            three::Key::N => Some(Command::Step),
+            three::Key::H => Some(Command::Autopilot(0)),
+            _ => None,
         }
-        vec
-    }
-
-    //Обновляем состояние игры
-    fn update(self, time_delta: f32, direction: Option<Direction>) -> GameController {
-        let game = self.game;
-        let game = match direction {
-            None => game,
-            Some(d) => game.handle_input(d)
-        }
-            .update(time_delta);
-        GameController { game }
     }
+}
 
-    pub fn get_max_score(&self) -> usize {
-        self.game.max_score.clone()
+impl InputSource<Action> for ThreeInput {
+    //Считываем все клавиши которые пользователь нажал с прошлого кадра и на основании их выбыраем команды.
+    //Порядок нажатий сохраняется чтобы быстрые повороты не терялись
+    fn commands(&mut self) -> Vec<Action> {
+        self.window.borrow().input.keys_hit().iter().filter_map(|k|
+            match *k {
+                three::Key::R => Some(Action::Resume),
+                three::Key::F12 => Some(Action::Screenshot),
+                key => ThreeInput::command(key).map(Action::Game),
+            }
+        ).collect()
     }
 
-    pub fn get_score(&self) -> usize {
-        self.game.score.clone()
+    fn is_quit(&self) -> bool {
+        self.window.borrow().input.hit(three::KEY_ESCAPE)
     }
 }
 
-//------------------------View ---------------
-//Представлие для отображение игры для пользователю и получение от него команд
-struct GameView {
-    controller: GameController,
-    window: three::Window,
+//Трехмерное представление игры в окне three
+struct ThreeRenderer {
+    window: Rc<RefCell<three::Window>>,
     camera: three::camera::Camera,
     ambient: three::light::Ambient,
     directional: three::light::Directional,
     font: Font,
-    current_score: Text,
+    //Текущий счет каждого игрока
+    scores: Vec<Text>,
     max_score: Text,
+    speed: Text,
+    status: Text,
+    message: Text,
 }
 
-impl GameView {
-    fn new() -> GameView {
-        let controller = GameController::new();
+impl ThreeRenderer {
+    fn new() -> ThreeRenderer {
 
         //Создаем окно в котором будет отображаться наша игра
         let mut window = three::Window::new("3D Snake Game By Victorem");
@@ -445,32 +215,45 @@ impl GameView {
         window.scene.add(&dir_light);
         //Загружаем из файла шрифт которым будет писать текст
         let font = window.factory.load_font("./DejaVuSans.ttf");
-        //Создаем текст на экране куда будет записывать текущий и максимальный счет
-        let current_score = window.factory.ui_text(&font, "0");
+        //Создаем текст на экране куда будет записывать максимальный счет.
+        //Текст счета каждого игрока создается когда игрок появляется в игре
         let mut max_score = window.factory.ui_text(&font, "0");
         max_score.set_pos([0.0, 40.0]);
-        window.scene.add(&current_score);
+        //Текст с текущим интервалом между шагами змейки
+        let mut speed = window.factory.ui_text(&font, "0");
+        speed.set_pos([0.0, 80.0]);
         window.scene.add(&max_score);
-        GameView { controller, window, camera, ambient: ambient_light, directional: dir_light, font, current_score, max_score }
-    }
-
-    //Считываем клавишу которую последней нажал пользователь и на основании ее выбыраем новое направление
-    fn get_input(&self) -> Option<Direction> {
-        match self.window.input.keys_hit().last() {
-            None => None,
-            Some(k) =>
-                match *k {
-                    three::Key::Left => Some(Direction::Left),
-                    three::Key::Right => Some(Direction::Right),
-                    three::Key::Down => Some(Direction::Top),
-                    three::Key::Up => Some(Direction::Bottom),
-                    _ => None,
-                }
+        //Текст рядом со счетом который показывает что игра на паузе
+        let mut status = window.factory.ui_text(&font, "");
+        status.set_pos([0.0, 120.0]);
+        window.scene.add(&status);
+        //Текст заставки, обратного отсчета и итогов игры
+        let mut message = window.factory.ui_text(&font, "");
+        message.set_pos([200.0, 250.0]);
+        window.scene.add(&speed);
+        window.scene.add(&message);
+        let window = Rc::new(RefCell::new(window));
+        ThreeRenderer { window, camera, ambient: ambient_light, directional: dir_light, font, scores: Vec::new(), max_score, speed, status, message }
+    }
+
+    //Клавиатура этого окна
+    fn input(&self) -> ThreeInput {
+        ThreeInput { window: self.window.clone() }
+    }
+
+    //Текст который нужно показать игроку на текущем этапе игры.
+    //После игры добавляем итоги каждого игрока
+    fn get_message(snapshot: &GameSnapshot) -> String {
+        let message = phase_message(&snapshot.phase);
+        match snapshot.phase {
+            Phase::GameOver(ref summary) => summary.players.iter().enumerate()
+                .fold(message, |m, (i, p)| format!("{}\n{} SCORE: {} LENGTH: {}", m, player_name(snapshot, i), p.score, p.length)),
+            _ => message,
         }
     }
 
     //Преобразуем модель полученную от контроллера в набор сеточных обьектов нашей сцены
-    fn get_meshes(self) -> (Vec<Mesh>, GameView) {
+    fn get_meshes(&self, snapshot: &GameSnapshot) -> Vec<Mesh> {
         //Создаем сферу
         let sphere = &three::Geometry::uv_sphere(0.5, 24, 24);
         //Создаем зеленое покрытие для нашей сферы с моделью освещения по Фонгу
@@ -490,78 +273,313 @@ impl GameView {
             color: three::color::RED | three::color::GREEN,
             glossiness: 80.0,
         };
+        //Змейки остальных игроков и компьютера красим по номеру: голова и хвост, как на картинках
+        let color = |c: Rgb| (c[0] as u32) << 16 | (c[1] as u32) << 8 | c[2] as u32;
+        let others: Vec<(three::material::Phong, three::material::Phong)> = Theme::default().others.into_iter()
+            .map(|(head, tail)| (
+                three::material::Phong { color: color(head), glossiness: 80.0 },
+                three::material::Phong { color: color(tail), glossiness: 80.0 },
+            ))
+            .collect();
+        //Особая еда. Бонусная голубая, ядовитая фиолетовая, золотая желтая
+        let cyan = &three::material::Phong {
+            color: three::color::GREEN | three::color::BLUE,
+            glossiness: 80.0,
+        };
+        let purple = &three::material::Phong {
+            color: 0x800080,
+            glossiness: 80.0,
+        };
+        let gold = &three::material::Phong {
+            color: 0xFFD700,
+            glossiness: 120.0,
+        };
+        //На поле без стен граница не опасна поэтому рисуем ее маленькими серыми точками позади поля
+        let small_sphere = &three::Geometry::uv_sphere(0.15, 8, 8);
+        let gray = &three::material::Basic {
+            color: 0x808080,
+            map: None,
+        };
+        let wrapped = snapshot.topology == Topology::Wrapped;
+        //Стены внутри фрейма рисуем серыми кубами
+        let cube = &three::Geometry::cuboid(0.9, 0.9, 0.9);
+        let white = &three::material::Phong {
+            color: 0xC0C0C0,
+            glossiness: 20.0,
+        };
 
         // Преобразуем нашу модель в сеточные обьекты
-        let mut view = self;
-        let meshes = view.controller.clone().get_state().iter().map(|s| {
+        let mut window = self.window.borrow_mut();
+        snapshot.points.iter().map(|s| {
             let state = s.clone();
             match state.state_type {
+                PointDtoType::Frame if wrapped => {
+                    let m = window.factory.mesh(small_sphere.clone(), gray.clone());
+                    m.set_position([state.x as f32, state.y as f32, -1.0]);
+                    m
+                }
                 PointDtoType::Frame => {
-                    let m = view.window.factory.mesh(sphere.clone(), blue.clone());
+                    let m = window.factory.mesh(sphere.clone(), blue.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::Wall => {
+                    let m = window.factory.mesh(cube.clone(), white.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::Tail if state.owner.map_or(false, |o| o > 0) => {
+                    let (_, tail) = &others[(state.owner.unwrap() - 1) % others.len()];
+                    let m = window.factory.mesh(sphere.clone(), tail.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::Head if state.owner.map_or(false, |o| o > 0) => {
+                    let (head, _) = &others[(state.owner.unwrap() - 1) % others.len()];
+                    let m = window.factory.mesh(sphere.clone(), head.clone());
                     m.set_position([state.x as f32, state.y as f32, 0.0]);
                     m
                 }
                 PointDtoType::Tail => {
-                    let m = view.window.factory.mesh(sphere.clone(), yellow.clone());
+                    let m = window.factory.mesh(sphere.clone(), yellow.clone());
                     m.set_position([state.x as f32, state.y as f32, 0.0]);
                     m
                 }
                 PointDtoType::Head => {
-                    let m = view.window.factory.mesh(sphere.clone(), red.clone());
+                    let m = window.factory.mesh(sphere.clone(), red.clone());
                     m.set_position([state.x as f32, state.y as f32, 0.0]);
                     m
                 }
                 PointDtoType::Food => {
-                    let m = view.window.factory.mesh(sphere.clone(), green.clone());
+                    let m = window.factory.mesh(sphere.clone(), green.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::BonusFood => {
+                    let m = window.factory.mesh(sphere.clone(), cyan.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::PoisonFood => {
+                    let m = window.factory.mesh(sphere.clone(), purple.clone());
+                    m.set_position([state.x as f32, state.y as f32, 0.0]);
+                    m
+                }
+                PointDtoType::GoldenFood => {
+                    let m = window.factory.mesh(sphere.clone(), gold.clone());
                     m.set_position([state.x as f32, state.y as f32, 0.0]);
                     m
                 }
             }
-        }).collect();
-        (meshes, view)
+        }).collect()
     }
+}
 
-    //Обновляем наше предстовление
-    fn update(self) -> GameView {
-        //Количество времени проешдшее с последнего обновления игры
-        let elapsed_time = self.window.input.delta_time();
-        let input = self.get_input();
-        let controller = self.controller.update(elapsed_time, input);
-        GameView { controller, ..self }
+impl Renderer for ThreeRenderer {
+    //Окно обрабатывает свои события и сообщает не закрыли ли его
+    fn is_open(&mut self) -> bool {
+        self.window.borrow_mut().update()
     }
 
     //Отображаем наше представление игроку
-    fn draw(self) -> GameView {
-        let (meshes, mut view) = self.get_meshes();
+    fn render(&mut self, snapshot: &GameSnapshot, status: &str) -> Result<(), Box<dyn Error>> {
+        //На заставке поле не показываем
+        let meshes = match snapshot.phase {
+            Phase::Title => Vec::new(),
+            _ => self.get_meshes(snapshot),
+        };
+        let mut window = self.window.borrow_mut();
         //Добавляем меши на сцену.
         for m in &meshes {
-            view.window.scene.add(m);
+            window.scene.add(m);
         }
         //Отрисовываем сцену на камеру
-        view.window.render(&view.camera);
+        window.render(&self.camera);
         //Очищаем сцену
         for m in meshes {
-            view.window.scene.remove(m);
+            window.scene.remove(m);
         }
         //Отображаем пользователю текущий счет
-        view.max_score.set_text(format!("MAX SCORE: {}", view.controller.get_max_score()));
-        view.current_score.set_text(format!("CURRENT SCORE: {}", view.controller.get_score()));
-        view
+        self.max_score.set_text(format!("MAX SCORE: {}", snapshot.max_score));
+        //Счет второго игрока пишем в правом верхнем углу, остальных ниже
+        while self.scores.len() < snapshot.scores.len() {
+            let i = self.scores.len();
+            let mut score = window.factory.ui_text(&self.font, "0");
+            score.set_pos([(i % 2) as f32 * 500.0, (i / 2) as f32 * 160.0]);
+            window.scene.add(&score);
+            self.scores.push(score);
+        }
+        if snapshot.scores.len() == 1 {
+            self.scores[0].set_text(format!("CURRENT SCORE: {}", snapshot.scores[0]));
+        } else {
+            for (i, score) in snapshot.scores.iter().enumerate() {
+                self.scores[i].set_text(format!("{} SCORE: {}", player_name(snapshot, i), score));
+            }
+        }
+        self.speed.set_text(format!("TICK: {:.0} MS", snapshot.tick_interval * 1000.0));
+        self.status.set_text(status);
+        self.message.set_text(ThreeRenderer::get_message(snapshot));
+        Ok(())
     }
+}
 
-    // Запускаем бесконечный цикл обновления и отрисовки игры
-    pub fn run(self) {
-        let mut view = self;
-        while view.window.update() && !view.window.input.hit(three::KEY_ESCAPE) {
-            view = view.update().draw();
+//Сохраняем рекорд если он побит
+fn save_max_score(controller: &GameController) {
+    let max_score = controller.get_max_score();
+    if max_score > ScoreRepository::load().unwrap_or(0) {
+        if let Err(e) = ScoreRepository::save(max_score) {
+            eprintln!("Can not save the max score: {}", e);
         }
     }
 }
 
-fn main() {
-    let view = GameView::new();
-    view.run();
+//Сохраняем кадр в PNG рядом с игрой. Имя файла содержит время чтобы кадры не затирали друг друга
+fn screenshot(snapshot: &GameSnapshot) {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = format!("./snapshot-{}.png", time);
+    match PictureRepository::save(&path, snapshot, SCREENSHOT_CELL, &Theme::default()) {
+        Ok(()) => println!("Saved {}", path),
+        Err(e) => eprintln!("Can not save {}: {}", path, e),
+    }
+}
+
+//Показываем игру в окне three пока игрок не выйдет.
+//Недоигранную локальную игру сохраняем чтобы ее можно было продолжить при следующем запуске
+fn show(source: Source) {
+    let mut renderer = ThreeRenderer::new();
+    let mut input = renderer.input();
+    match run(source, &mut renderer, &mut input) {
+        Ok(Source::Local(ref controller, _)) => {
+            save_max_score(controller);
+            if controller.is_in_progress() {
+                if let Err(e) = SaveRepository::save(SAVE_FILE, &controller.game, controller.get_replay()) {
+                    eprintln!("Can not save the game: {}", e);
+                }
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+//Рисуем запись на процессоре и сохраняем в GIF или APNG.
+//Параметры --fps, --cell, --theme dark|light|retro и --from, --to с номерами шагов записи
+fn export(args: &[String], output: &str, replay: &Replay) {
+    let default = AnimationConfig::default();
+    let config = AnimationConfig {
+        fps: or_exit(parse(args, "--fps", default.fps)),
+        cell: or_exit(parse(args, "--cell", default.cell)),
+        theme: or_exit(parse(args, "--theme", default.theme)),
+        from: or_exit(parse(args, "--from", default.from)),
+        to: or_exit(parse_option(args, "--to")),
+    };
+    match AnimationRepository::save(output, replay, &config) {
+        Ok(frames) => println!("Saved {} frames to {}", frames, output),
+        Err(e) => {
+            eprintln!("Can not export replay: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+//Папка с уровнями игры
+const LEVELS_DIRECTORY: &str = "./levels";
+
+//Загружаем уровень выбранный параметром --level <имя>.
+//Без параметра берем classic из папки уровней или пустое поле если ее нет
+fn load_level(args: &[String]) -> Level {
+    let name = match option(args, "--level") {
+        Some(n) => n.clone(),
+        None => "classic".to_string(),
+    };
+    let path = format!("{}/{}.txt", LEVELS_DIRECTORY, name);
+    match LevelRepository::load(&path) {
+        Ok(level) => level,
+        Err(e) => {
+            if args.iter().any(|a| a == "--level") {
+                eprintln!("Can not load level: {}", e);
+                if let Ok(names) = LevelRepository::list(LEVELS_DIRECTORY) {
+                    eprintln!("Available levels: {}", names.join(", "));
+                }
+                std::process::exit(1);
+            }
+            Level::default()
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests;
+//Неверный параметр командной строки завершает программу с понятной ошибкой
+fn or_exit<T>(value: Result<T, String>) -> T {
+    value.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    //Параметр --wrap включает поле без стен
+    let topology = if args.iter().any(|a| a == "--wrap") {
+        Topology::Wrapped
+    } else {
+        Topology::Walled
+    };
+    let level = load_level(&args);
+    //Параметр --food <количество> выкладывает на поле несколько видов еды сразу
+    let food = or_exit(parse_option::<usize>(&args, "--food"))
+        .map_or(FoodConfig::default(), FoodConfig::mixed);
+    //Параметр --difficulty easy|normal|hard|classic задает скорость змейки
+    let difficulty: Difficulty = or_exit(parse(&args, "--difficulty", Difficulty::default()));
+    //Параметр --connect <адрес> подключается к серверу вместо локальной игры.
+    //Порт можно не указывать, тогда берется порт сервера по умолчанию
+    if let Some(address) = option(&args, "--connect") {
+        let address = if address.contains(':') { address.clone() } else { format!("{}:{}", address, DEFAULT_PORT) };
+        let remote = RemoteGame::connect(address.as_str()).unwrap_or_else(|e| {
+            eprintln!("Can not connect to {}: {}", address, e);
+            std::process::exit(1);
+        });
+        show(Source::Remote(remote));
+        return;
+    }
+    //Параметр --replay <файл> показывает записанную игру. Последняя игра всегда лежит в last.replay
+    if let Some(path) = option(&args, "--replay") {
+        let replay = ReplayRepository::load(path).unwrap_or_else(|e| {
+            eprintln!("Can not load replay: {}", e);
+            std::process::exit(1);
+        });
+        //Параметр --export <файл.gif|файл.png> сохраняет запись как анимацию без окна
+        if let Some(output) = option(&args, "--export") {
+            export(&args, output, &replay);
+            return;
+        }
+        show(Source::Replay(ReplayPlayer::new(replay)));
+        return;
+    }
+    //Параметр --players 2 добавляет второго игрока на той же клавиатуре
+    let players: usize = or_exit(parse(&args, "--players", 1));
+    if !(1..=2).contains(&players) {
+        eprintln!("Unknown number of players '{}', expected 1 or 2", players);
+        std::process::exit(1);
+    }
+    //Параметр --bots <количество> добавляет змейки компьютера, а --ai greedy|cautious|safe задает как они играют
+    let bots: usize = or_exit(parse(&args, "--bots", 0));
+    let ai: AiLevel = or_exit(parse(&args, "--ai", AiLevel::default()));
+    let controller = GameController::new(&level)
+        .with_topology(topology)
+        .with_food_config(food)
+        .with_speed(difficulty.speed_curve())
+        .with_max_score(ScoreRepository::load().unwrap_or(0))
+        .with_players(players)
+        .with_bots(bots, ai);
+    //Параметр --autopilot сразу отдает змейку первого игрока автопилоту. Удобно для демонстрации
+    let controller = if args.iter().any(|a| a == "--autopilot") {
+        controller.update(0.0, vec![Command::Autopilot(0)])
+    } else {
+        controller
+    };
+    let saved = SaveRepository::load(SAVE_FILE).ok();
+    show(Source::Local(controller, saved));
+}
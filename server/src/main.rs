@@ -0,0 +1,55 @@
+/*Сервер сетевой игры. Владеет одной игрой на несколько змеек,
+принимает клиентов по TCP и каждый шаг рассылает им состояние игры.
+Клиент это обычная игра запущенная с параметром --connect <адрес>.
+*/
+extern crate snake_core;
+
+use snake_core::{option, parse, parse_option, AiLevel, Difficulty, FoodConfig, GameController, GameServer, Level, LevelRepository, Topology, DEFAULT_PORT};
+
+//Папка с уровнями игры
+const LEVELS_DIRECTORY: &str = "./levels";
+
+//Неверный параметр командной строки завершает программу с понятной ошибкой
+fn or_exit<T>(value: Result<T, String>) -> T {
+    value.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let level = match option(&args, "--level") {
+        None => Level::default(),
+        Some(name) => LevelRepository::load(&format!("{}/{}.txt", LEVELS_DIRECTORY, name)).unwrap_or_else(|e| {
+            eprintln!("Can not load level: {}", e);
+            std::process::exit(1);
+        }),
+    };
+    let topology = if args.iter().any(|a| a == "--wrap") {
+        Topology::Wrapped
+    } else {
+        Topology::Walled
+    };
+    let food = or_exit(parse_option::<usize>(&args, "--food"))
+        .map_or(FoodConfig::default(), FoodConfig::mixed);
+    let port: u16 = or_exit(parse(&args, "--port", DEFAULT_PORT));
+    let players: usize = or_exit(parse(&args, "--players", 2));
+    let bots: usize = or_exit(parse(&args, "--bots", 0));
+    let ai: AiLevel = or_exit(parse(&args, "--ai", AiLevel::default()));
+    let difficulty: Difficulty = or_exit(parse(&args, "--difficulty", Difficulty::default()));
+    let controller = GameController::new(&level)
+        .with_topology(topology)
+        .with_food_config(food)
+        .with_speed(difficulty.speed_curve())
+        .with_players(players)
+        .with_bots(bots, ai);
+    //Без параметра --host сервер доступен только с этого компьютера
+    let host = option(&args, "--host").cloned().unwrap_or_else(|| "127.0.0.1".to_string());
+    let server = GameServer::bind((host.as_str(), port), controller).unwrap_or_else(|e| {
+        eprintln!("Can not start the server on {}:{}: {}", host, port, e);
+        std::process::exit(1);
+    });
+    println!("Snake server for {} players is listening on {}:{}", players, host, port);
+    server.run();
+}
@@ -0,0 +1,167 @@
+/*Пакетный симулятор. Играет много игр без окна и печатает итоги каждой игры и сводку.
+Змейкой первого игрока управляет встроенный компьютерный игрок или внешняя программа.
+Нужен чтобы проверять изменения компьютерных игроков и правил игры.
+*/
+extern crate snake_core;
+#[macro_use]
+extern crate serde_json;
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use snake_core::{option, parse, parse_option, simulate, AiLevel, BuiltinPolicy, Difficulty, Direction, FoodConfig, Game, GameController, GameReport, Level, LevelRepository, Policy, Statistics, Topology};
+
+//Папка с уровнями игры
+const LEVELS_DIRECTORY: &str = "./levels";
+
+//Внешняя программа которая управляет змейкой.
+//На каждом шаге она получает снимок игры одной строкой JSON
+//и отвечает строкой с направлением: top, bottom, left, right или пустой строкой чтобы ехать прямо
+struct ExternalPolicy {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl ExternalPolicy {
+    fn spawn(command: &str) -> Result<ExternalPolicy, Box<dyn std::error::Error>> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("empty policy command")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let input = child.stdin.take().ok_or("policy has no stdin")?;
+        let output = BufReader::new(child.stdout.take().ok_or("policy has no stdout")?);
+        Ok(ExternalPolicy { child, input, output })
+    }
+}
+
+impl Policy for ExternalPolicy {
+    fn choose(&mut self, game: &Game, _: usize) -> Option<Direction> {
+        let snapshot = GameController::from_game(game.clone()).snapshot();
+        let mut answer = String::new();
+        let asked = serde_json::to_string(&snapshot).map_err(|e| e.to_string())
+            .and_then(|line| writeln!(self.input, "{}", line).map_err(|e| e.to_string()))
+            .and_then(|_| self.input.flush().map_err(|e| e.to_string()))
+            .and_then(|_| self.output.read_line(&mut answer).map_err(|e| e.to_string()));
+        match asked {
+            Ok(0) | Err(_) => {
+                eprintln!("Policy program stopped answering");
+                std::process::exit(1);
+            }
+            Ok(_) => {}
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "top" => Some(Direction::Top),
+            "bottom" => Some(Direction::Bottom),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for ExternalPolicy {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//Играем все игры одной политикой. Игра с номером i начинается с зерна seed + i
+fn run<P: Policy>(policy: &mut P, start: &dyn Fn(u64) -> Game, seed: u64, games: u64, time_delta: f32, max_ticks: u64) -> Vec<GameReport> {
+    (0..games)
+        .map(|i| simulate(start(seed.wrapping_add(i)), policy, time_delta, max_ticks))
+        .map(|game| GameReport::new(&game))
+        .collect()
+}
+
+fn print_csv(reports: &[GameReport], statistics: &Statistics) {
+    println!("game,seed,score,length,ticks,cause");
+    for (i, r) in reports.iter().enumerate() {
+        println!("{},{},{},{},{},{}", i, r.seed, r.score, r.length, r.ticks, r.cause);
+    }
+    //Сводка идет в поток ошибок чтобы CSV можно было сразу отдать другой программе
+    eprintln!("games: {}", statistics.games);
+    eprintln!("score: mean {:.2}, min {}, max {}", statistics.mean_score, statistics.min_score, statistics.max_score);
+    eprintln!("length: mean {:.2}", statistics.mean_length);
+    eprintln!("ticks: mean {:.2}", statistics.mean_ticks);
+    for (cause, count) in statistics.causes.iter() {
+        eprintln!("{}: {}", cause, count);
+    }
+}
+
+fn print_json(reports: &[GameReport], statistics: &Statistics) {
+    match serde_json::to_string_pretty(&json!({ "games": reports, "statistics": statistics })) {
+        Ok(text) => println!("{}", text),
+        Err(e) => eprintln!("Can not write JSON: {}", e),
+    }
+}
+
+//Неверный параметр командной строки завершает программу с понятной ошибкой
+fn or_exit<T>(value: Result<T, String>) -> T {
+    value.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let level = match option(&args, "--level") {
+        None => Level::default(),
+        Some(name) => LevelRepository::load(&format!("{}/{}.txt", LEVELS_DIRECTORY, name)).unwrap_or_else(|e| {
+            eprintln!("Can not load level: {}", e);
+            std::process::exit(1);
+        }),
+    };
+    let topology = if args.iter().any(|a| a == "--wrap") {
+        Topology::Wrapped
+    } else {
+        Topology::Walled
+    };
+    let food = or_exit(parse_option::<usize>(&args, "--food"))
+        .map_or(FoodConfig::default(), FoodConfig::mixed);
+    let games: u64 = or_exit(parse(&args, "--games", 100));
+    let seed: u64 = or_exit(parse(&args, "--seed", 0));
+    let max_ticks: u64 = or_exit(parse(&args, "--max-ticks", 100_000));
+    let time_delta: f32 = or_exit(parse(&args, "--delta", 1.0 / 60.0));
+    if time_delta <= 0.0 {
+        eprintln!("--delta must be positive");
+        std::process::exit(1);
+    }
+    let bots: usize = or_exit(parse(&args, "--bots", 0));
+    let ai: AiLevel = or_exit(parse(&args, "--ai", AiLevel::default()));
+    let difficulty: Difficulty = or_exit(parse(&args, "--difficulty", Difficulty::default()));
+    let start = |seed: u64| GameController::with_seed(&level, seed)
+        .with_topology(topology)
+        .with_food_config(food.clone())
+        .with_speed(difficulty.speed_curve())
+        .with_bots(bots, ai)
+        .game;
+    //Параметр --policy greedy|cautious|safe|autopilot выбирает встроенного игрока,
+    //а --exec "<программа> <параметры>" подключает внешнюю программу
+    let reports = match option(&args, "--exec") {
+        Some(command) => {
+            let mut policy = ExternalPolicy::spawn(command).unwrap_or_else(|e| {
+                eprintln!("Can not start '{}': {}", command, e);
+                std::process::exit(1);
+            });
+            run(&mut policy, &start, seed, games, time_delta, max_ticks)
+        }
+        None => {
+            let level: AiLevel = or_exit(parse(&args, "--policy", AiLevel::TailSafe));
+            run(&mut BuiltinPolicy(level), &start, seed, games, time_delta, max_ticks)
+        }
+    };
+    let statistics = Statistics::new(&reports);
+    match option(&args, "--format").map(|f| f.as_str()) {
+        None | Some("csv") => print_csv(&reports, &statistics),
+        Some("json") => print_json(&reports, &statistics),
+        Some(f) => {
+            eprintln!("Unknown format '{}', expected csv or json", f);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//Business Logic Layer. Змейки под управлением компьютера -----------------------
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use entities::{Direction, Point, Topology};
+use logic::Game;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Насколько осторожно играет компьютер
+pub enum AiLevel {
+    //Идет к ближайшей еде кратчайшим путем и думает только о следующем шаге
+    Greedy,
+    //Как жадный, но обходит клетки рядом с головами других змеек
+    //и не заходит туда где ему не хватит места
+    #[default]
+    Cautious,
+    //Идет к еде только если после нее сможет добраться до своего хвоста,
+    //иначе ползет за хвостом пока путь не освободится
+    TailSafe,
+    //Автопилот. Ползет по гамильтонову циклу через все клетки поля и срезает путь когда это безопасно.
+    //Так змейка рано или поздно заполняет все поле. Если цикла нет то играет как TailSafe
+    Autopilot,
+}
+
+impl std::str::FromStr for AiLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AiLevel, String> {
+        match s.to_lowercase().as_str() {
+            "greedy" => Ok(AiLevel::Greedy),
+            "cautious" => Ok(AiLevel::Cautious),
+            "safe" => Ok(AiLevel::TailSafe),
+            "autopilot" => Ok(AiLevel::Autopilot),
+            _ => Err(format!("unknown AI level '{}', expected greedy, cautious, safe or autopilot", s)),
+        }
+    }
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Right, Direction::Bottom, Direction::Left, Direction::Top];
+
+//Сколько клеток цикла автопилот оставляет между головой и хвостом когда срезает путь
+const SHORTCUT_MARGIN: usize = 4;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+//Гамильтонов цикл через все клетки прямоугольного поля без стен.
+//Первая строка идет слева направо, остальные строки змейкой без первого столбца,
+//а по первому столбцу цикл возвращается в начало. Для этого число строк должно быть четным,
+//иначе строки и столбцы меняются местами. Номер клетки в цикле считается без таблицы
+pub struct HamiltonianCycle {
+    min_x: usize,
+    min_y: usize,
+    //Размеры поля вдоль строки и поперек строк цикла
+    columns: usize,
+    rows: usize,
+    //Строки цикла идут вдоль оси y
+    transposed: bool,
+}
+
+impl HamiltonianCycle {
+    //Цикл по полю игры. Его нет если на поле есть стены или обе стороны поля нечетные:
+    //клетки поля раскрашиваются как шахматная доска, и цикл через нечетное число клеток невозможен
+    pub fn new(game: &Game) -> Option<HamiltonianCycle> {
+        let (min_x, min_y, max_x, max_y) = game.food_generator.bounds()?;
+        let inside = |p: &Point| p.x >= min_x && p.x <= max_x && p.y >= min_y && p.y <= max_y;
+        if game.walls.iter().any(inside) {
+            return None;
+        }
+        let width = (max_x - min_x) as usize + 1;
+        let height = (max_y - min_y) as usize + 1;
+        let (columns, rows, transposed) = if height.is_multiple_of(2) {
+            (width, height, false)
+        } else {
+            (height, width, true)
+        };
+        if !rows.is_multiple_of(2) || columns < 2 {
+            return None;
+        }
+        Some(HamiltonianCycle { min_x: min_x as usize, min_y: min_y as usize, columns, rows, transposed })
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //Номер клетки в цикле
+    pub fn index(&self, point: &Point) -> usize {
+        let (x, y) = (point.x as usize - self.min_x, point.y as usize - self.min_y);
+        let (column, row) = if self.transposed { (y, x) } else { (x, y) };
+        let returning = self.columns + (self.rows - 1) * (self.columns - 1);
+        if row == 0 {
+            column
+        } else if column == 0 {
+            returning + (self.rows - 1 - row)
+        } else {
+            let start = self.columns + (row - 1) * (self.columns - 1);
+            if row % 2 == 1 {
+                start + (self.columns - 1 - column)
+            } else {
+                start + (column - 1)
+            }
+        }
+    }
+
+    //Сколько шагов по циклу от одной клетки до другой
+    pub fn distance(&self, from: &Point, to: &Point) -> usize {
+        (self.index(to) + self.len() - self.index(from)) % self.len()
+    }
+}
+
+//Выбираем куда повернуть змейку игрока на следующем шаге.
+//Ничего не возвращаем если змейке уже некуда деться
+pub fn choose_direction(game: &Game, player: usize, level: AiLevel) -> Option<Direction> {
+    let snake = &game.players[player].snake;
+    let head = snake.head();
+    let free = |p: &Point| game.grid.cell(p).snake == 0;
+    match level {
+        AiLevel::Greedy => {
+            match find_path(game, &head, &free, &|p| is_food(game, p)) {
+                Some(path) => direction_to(game, &head, &path[0]),
+                None => roomiest_direction(game, &head, &free),
+            }
+        }
+        AiLevel::Cautious => {
+            let danger = danger_cells(game, player);
+            let passable = |p: &Point| free(p) && !danger.contains(p);
+            let path = find_path(game, &head, &passable, &|p| is_food(game, p))
+                .filter(|path| flood_fill(game, &path[0], &passable) >= snake.points.len());
+            match path {
+                Some(path) => direction_to(game, &head, &path[0]),
+                None => roomiest_direction(game, &head, &passable)
+                    .or_else(|| roomiest_direction(game, &head, &free)),
+            }
+        }
+        AiLevel::Autopilot => match HamiltonianCycle::new(game) {
+            Some(cycle) => follow_cycle(game, player, &cycle),
+            None => choose_direction(game, player, AiLevel::TailSafe),
+        },
+        AiLevel::TailSafe => {
+            let path = find_path(game, &head, &free, &|p| is_food(game, p))
+                .filter(|path| can_reach_tail_after(game, player, path));
+            match path {
+                Some(path) => direction_to(game, &head, &path[0]),
+                None => chase_tail(game, player, &free)
+                    .or_else(|| roomiest_direction(game, &head, &free)),
+            }
+        }
+    }
+}
+
+fn is_food(game: &Game, point: &Point) -> bool {
+    game.grid.cell(point).food
+}
+
+//Соседние клетки в которые голова может шагнуть не разбившись о стену или фрейм
+fn neighbors(game: &Game, point: &Point) -> Vec<(Direction, Point)> {
+    DIRECTIONS.iter()
+        .map(|d| (d.clone(), point.step(d, &game.frame, game.topology)))
+        .filter(|(_, p)| p != point)
+        .filter(|(_, p)| !game.grid.cell(p).wall)
+        .filter(|(_, p)| game.topology == Topology::Wrapped || !game.frame.intersects(p))
+        .collect()
+}
+
+fn direction_to(game: &Game, from: &Point, to: &Point) -> Option<Direction> {
+    neighbors(game, from).into_iter().find(|(_, p)| p == to).map(|(d, _)| d)
+}
+
+//Поиск в ширину кратчайшего пути до ближайшей клетки удовлетворяющей goal.
+//Путь не включает начальную клетку
+fn find_path(game: &Game, start: &Point, passable: &dyn Fn(&Point) -> bool, goal: &dyn Fn(&Point) -> bool) -> Option<Vec<Point>> {
+    let mut parents: HashMap<Point, Point> = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start.clone());
+    while let Some(current) = queue.pop_front() {
+        for (_, next) in neighbors(game, &current) {
+            if next == *start || parents.contains_key(&next) {
+                continue;
+            }
+            if goal(&next) {
+                parents.insert(next.clone(), current);
+                let mut path = vec![next];
+                while let Some(parent) = parents.get(path.last().unwrap()) {
+                    if parent == start {
+                        break;
+                    }
+                    path.push(parent.clone());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if passable(&next) {
+                parents.insert(next.clone(), current.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+//Сколько клеток доступно из заданной включая ее саму
+fn flood_fill(game: &Game, start: &Point, passable: &dyn Fn(&Point) -> bool) -> usize {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start.clone());
+    queue.push_back(start.clone());
+    while let Some(current) = queue.pop_front() {
+        for (_, next) in neighbors(game, &current) {
+            if passable(&next) && visited.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    visited.len()
+}
+
+//Шаг в соседнюю клетку из которой доступно больше всего места
+fn roomiest_direction(game: &Game, head: &Point, passable: &dyn Fn(&Point) -> bool) -> Option<Direction> {
+    neighbors(game, head).into_iter()
+        .filter(|(_, p)| passable(p))
+        .map(|(d, p)| (flood_fill(game, &p, passable), d))
+        .fold(None, |best: Option<(usize, Direction)>, (room, d)| match best {
+            Some((best_room, _)) if best_room >= room => best,
+            _ => Some((room, d)),
+        })
+        .map(|(_, d)| d)
+}
+
+//Клетки в которые на следующем шаге может шагнуть голова другой живой змейки
+fn danger_cells(game: &Game, player: usize) -> HashSet<Point> {
+    game.players.iter().enumerate()
+        .filter(|(i, p)| *i != player && p.is_alive())
+        .flat_map(|(_, p)| neighbors(game, &p.snake.head()))
+        .map(|(_, p)| p)
+        .collect()
+}
+
+//Проходим путь до еды на копии змейки и проверяем что после этого голова сможет добраться до хвоста
+fn can_reach_tail_after(game: &Game, player: usize, path: &[Point]) -> bool {
+    let snake = &game.players[player].snake;
+    let mut body = snake.points.clone();
+    for (i, p) in path.iter().enumerate() {
+        body.push_front(p.clone());
+        //На последнем шаге змейка съедает еду и вырастает
+        if i + 1 < path.len() {
+            body.pop_back();
+        }
+    }
+    let (head, tail) = match (body.front(), body.back()) {
+        (Some(head), Some(tail)) => (head.clone(), tail.clone()),
+        _ => return false,
+    };
+    let own: HashSet<&Point> = body.iter().collect();
+    let before: HashSet<&Point> = snake.points.iter().collect();
+    let passable = |p: &Point| !own.contains(p) && (game.grid.cell(p).snake == 0 || before.contains(p));
+    find_path(game, &head, &passable, &|p| *p == tail).is_some()
+}
+
+//Ползем к своему хвосту. Хвост освобождает клетку на каждом шаге поэтому этот путь безопасен.
+//После роста хвост стоит на месте несколько шагов, поэтому путь должен быть не короче этого
+fn chase_tail(game: &Game, player: usize, passable: &dyn Fn(&Point) -> bool) -> Option<Direction> {
+    let snake = &game.players[player].snake;
+    let head = snake.head();
+    let tail = snake.points.back()?.clone();
+    let stacked = game.grid.cell(&tail).snake as usize;
+    let path = find_path(game, &head, passable, &|p| *p == tail)
+        .filter(|path| path.len() >= stacked)?;
+    direction_to(game, &head, &path[0])
+}
+
+//Следующий шаг автопилота. Пока змейка занимает меньше половины поля она может срезать путь
+//в клетку дальше по циклу, если не перепрыгнет еду и останется достаточно далеко от своего хвоста.
+//Тело змейки при этом всегда лежит вдоль цикла позади головы и голова в него не врежется
+fn follow_cycle(game: &Game, player: usize, cycle: &HamiltonianCycle) -> Option<Direction> {
+    let snake = &game.players[player].snake;
+    let head = snake.head();
+    let tail = snake.points.back()?.clone();
+    let free = |p: &Point| game.grid.cell(p).snake == 0;
+    let candidates: Vec<(Direction, Point)> = neighbors(game, &head).into_iter()
+        .filter(|(_, p)| free(p))
+        .collect();
+    let next = candidates.iter().find(|(_, p)| cycle.distance(&head, p) == 1);
+    if snake.points.len() * 2 >= cycle.len() {
+        return match next {
+            Some((d, _)) => Some(d.clone()),
+            None => choose_direction(game, player, AiLevel::TailSafe),
+        };
+    }
+    //Пока хвост стоит на месте после роста он ближе к голове на число лишних точек
+    let growing = (game.grid.cell(&tail).snake as usize).saturating_sub(1);
+    let to_tail = cycle.distance(&head, &tail);
+    let to_food = game.foods.iter()
+        .map(|f| cycle.distance(&head, &f.point))
+        .min()
+        .unwrap_or(to_tail);
+    let limit = to_tail.saturating_sub(SHORTCUT_MARGIN + growing);
+    let shortcut = candidates.iter()
+        .map(|(d, p)| (cycle.distance(&head, p), d))
+        .filter(|(distance, _)| *distance > 0 && *distance <= to_food && *distance < limit)
+        .max_by_key(|(distance, _)| *distance)
+        .map(|(_, d)| d.clone());
+    shortcut
+        .or_else(|| next.map(|(d, _)| d.clone()))
+        .or_else(|| choose_direction(game, player, AiLevel::TailSafe))
+}
@@ -0,0 +1,291 @@
+//Application Layer--------------------------------------------------------------
+
+use ai::AiLevel;
+use entities::{Coord, FoodConfig, FoodKind, Point, SpeedCurve, Topology};
+use logic::{Command, Game, Phase};
+use level::Level;
+use replay::Replay;
+
+// --- Model ----
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub enum PointDtoType {
+    Head,
+    Tail,
+    Food,
+    BonusFood,
+    PoisonFood,
+    GoldenFood,
+    #[default]
+    Frame,
+    Wall,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Модель котору будет видеть представление для отображения пользователю.
+pub struct PointDto {
+    pub x: Coord,
+    pub y: Coord,
+    pub state_type: PointDtoType,
+    //Номер игрока которому принадлежит точка змейки. У еды, стен и фрейма владельца нет
+    pub owner: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+//Все что нужно представлению чтобы нарисовать один кадр.
+//Снимок можно получить от локального контроллера или от сервера по сети
+pub struct GameSnapshot {
+    pub phase: Phase,
+    pub points: Vec<PointDto>,
+    pub topology: Topology,
+    pub scores: Vec<usize>,
+    pub max_score: usize,
+    pub tick_interval: f32,
+    //Какими змейками управляет компьютер и какими автопилот, по порядку номеров игроков
+    pub bots: Vec<bool>,
+    pub autopilots: Vec<bool>,
+}
+
+//------------------------------Controller -----------------------------
+#[derive(Debug, Clone, Default)]
+// Контроллер который будет посредником между представлением и логикой нашей игры
+pub struct GameController {
+    pub game: Game,
+    //Запись текущей или последней законченной игры
+    recording: Option<Replay>,
+}
+
+impl GameController {
+    //Контроллер для игры на заданном уровне
+    pub fn new(level: &Level) -> GameController {
+        GameController::with_seed(level, rand::random())
+    }
+
+    //Контроллер с заранее известным зерном чтобы игру можно было повторить.
+    //Игра начинается с заставки
+    pub fn with_seed(level: &Level, seed: u64) -> GameController {
+        GameController { game: Game::from_level(level, seed).with_phase(Phase::Title), recording: None }
+    }
+
+    //Контроллер для уже собранной игры
+    pub fn from_game(game: Game) -> GameController {
+        GameController { game, recording: None }
+    }
+
+    //Контроллер для сохраненной игры. Игра продолжается с паузы чтобы игрок успел приготовиться.
+    //Запись продолжается с того места где ее сохранили, поэтому законченную игру можно повторить с начала
+    pub fn resumed(game: Game, recording: Option<Replay>) -> GameController {
+        let game = match game.phase {
+            Phase::Playing => game.with_phase(Phase::Paused),
+            _ => game,
+        };
+        GameController { game, recording }
+    }
+
+    //Игру стоит сохранить при выходе: она уже началась и еще не закончилась
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self.game.phase, Phase::Countdown { .. } | Phase::Playing | Phase::Paused)
+    }
+
+    //Добавляет второго игрока. Его змейка начинает напротив змейки первого игрока
+    pub fn with_second_player(self) -> GameController {
+        let snake = self.game.mirrored_snake();
+        GameController { game: self.game.with_player(snake), ..self }
+    }
+
+    //Добавляет игроков пока их не станет count. Если на поле не хватает места то добавляется сколько поместилось
+    pub fn with_players(self, count: usize) -> GameController {
+        let mut game = self.game;
+        while game.players.len() < count {
+            match game.free_snake() {
+                Some(snake) => game = game.with_player(snake),
+                None => break,
+            }
+        }
+        GameController { game, ..self }
+    }
+
+    //Добавляет змейки под управлением компьютера. Если на поле не хватает места то добавляется сколько поместилось
+    pub fn with_bots(self, count: usize, level: AiLevel) -> GameController {
+        let mut game = self.game;
+        for _ in 0..count {
+            match game.free_snake() {
+                Some(snake) => {
+                    let player = game.players.len();
+                    game = game.with_player(snake).with_bot(player, Some(level));
+                }
+                None => break,
+            }
+        }
+        GameController { game, ..self }
+    }
+
+    //Меняет форму поля игры
+    pub fn with_topology(self, topology: Topology) -> GameController {
+        GameController { game: self.game.with_topology(topology), ..self }
+    }
+
+    //Добавляет стены внутри фрейма
+    pub fn with_walls(self, walls: Vec<Point>) -> GameController {
+        GameController { game: self.game.with_walls(walls), ..self }
+    }
+
+    //Задает сколько еды лежит на поле и какая она
+    pub fn with_food_config(self, config: FoodConfig) -> GameController {
+        GameController { game: self.game.with_food_config(config), ..self }
+    }
+
+    //Задает как меняется скорость змейки. Готовые варианты дает Difficulty::speed_curve
+    pub fn with_speed(self, speed: SpeedCurve) -> GameController {
+        GameController { game: self.game.with_speed(speed), ..self }
+    }
+
+    //Рекорд прошлых игр, например загруженный из ScoreRepository
+    pub fn with_max_score(self, max_score: usize) -> GameController {
+        GameController { game: self.game.with_max_score(max_score), ..self }
+    }
+
+    //Сколько секунд сейчас проходит между шагами змейки
+    pub fn get_tick_interval(&self) -> f32 {
+        self.game.tick_interval()
+    }
+
+    pub fn get_topology(&self) -> Topology {
+        self.game.topology
+    }
+
+    //Получить коллекцию точек которые нужно отрисовать в данный момент
+    pub fn get_state(&self) -> Vec<PointDto> {
+        //Длинная змейка дает тысячи точек, поэтому память выделяем сразу
+        let frame = &self.game.frame;
+        let capacity = self.game.foods.len()
+            + self.game.players.iter().map(|p| p.snake.points.len()).sum::<usize>()
+            + self.game.walls.len()
+            + 2 * ((frame.max_x - frame.min_x) as usize + (frame.max_y - frame.min_y) as usize + 2);
+        let mut vec: Vec<PointDto> = Vec::with_capacity(capacity);
+        for f in self.game.foods.iter() {
+            let state_type = match f.kind {
+                FoodKind::Normal => PointDtoType::Food,
+                FoodKind::Bonus => PointDtoType::BonusFood,
+                FoodKind::Poison => PointDtoType::PoisonFood,
+                FoodKind::Golden => PointDtoType::GoldenFood,
+            };
+            vec.push(PointDto { x: f.point.x, y: f.point.y, state_type, owner: None });
+        }
+        for (owner, player) in self.game.players.iter().enumerate() {
+            let head = player.snake.head();
+            vec.push(PointDto { x: head.x, y: head.y, state_type: PointDtoType::Head, owner: Some(owner) });
+            //Все точки за исключением головы змеи
+            for p in player.snake.points.iter().filter(|p| **p != head) {
+                vec.push(PointDto { x: p.x, y: p.y, state_type: PointDtoType::Tail, owner: Some(owner) });
+            }
+        }
+        //Стены внутри фрейма
+        for w in self.game.walls.iter() {
+            vec.push(PointDto { x: w.x, y: w.y, state_type: PointDtoType::Wall, owner: None });
+        }
+        //горизонтальные линии фрейма
+        for x in self.game.frame.min_x..=self.game.frame.max_x {
+            vec.push(PointDto { x, y: self.game.frame.max_y, state_type: PointDtoType::Frame, owner: None });
+            vec.push(PointDto { x, y: self.game.frame.min_y, state_type: PointDtoType::Frame, owner: None });
+        }
+        //Вериткальные линии фрейма
+        for y in self.game.frame.min_y..=self.game.frame.max_y {
+            vec.push(PointDto { x: self.game.frame.max_x, y, state_type: PointDtoType::Frame, owner: None });
+            vec.push(PointDto { x: self.game.frame.min_x, y, state_type: PointDtoType::Frame, owner: None });
+        }
+        vec
+    }
+
+    //Обновляем состояние игры. Команды применяются по порядку нажатия
+    pub fn update(self, time_delta: f32, commands: Vec<Command>) -> GameController {
+        let controller = commands.into_iter().fold(self, GameController::apply);
+        let game = controller.game.update(time_delta);
+        GameController { game, ..controller }.record_ticks()
+    }
+
+    //Применяем команды и делаем ровно один шаг змейками независимо от времени.
+    //Так двигается повтор записанной игры
+    pub fn advance(self, commands: Vec<Command>) -> GameController {
+        let controller = commands.into_iter().fold(self, GameController::apply);
+        let game = controller.game.advance();
+        GameController { game, ..controller }.record_ticks()
+    }
+
+    //Применяем одну команду и записываем ее если она может изменить ход игры.
+    //Новая запись начинается вместе с новой игрой
+    fn apply(self, command: Command) -> GameController {
+        let recorded = matches!(
+            (&self.game.phase, &command),
+            (Phase::Playing, Command::Turn(..))
+                | (Phase::Paused, Command::Turn(..))
+                | (Phase::Countdown { .. }, Command::Autopilot(_))
+                | (Phase::Playing, Command::Autopilot(_))
+                | (Phase::Paused, Command::Autopilot(_))
+        );
+        let starting = matches!(self.game.phase, Phase::Title | Phase::GameOver(_));
+        let tick = self.game.ticks;
+        let game = self.game.handle_command(command.clone());
+        let recording = match self.recording {
+            _ if starting && matches!(game.phase, Phase::Countdown { .. }) => Some(Replay::record(&game)),
+            Some(replay) if recorded => Some(replay.with_input(tick, command)),
+            recording => recording,
+        };
+        GameController { game, recording }
+    }
+
+    //Запись знает сколько шагов уже сделано в игре
+    fn record_ticks(self) -> GameController {
+        let ticks = self.game.ticks;
+        let recording = self.recording.map(|replay| Replay { ticks, ..replay });
+        GameController { recording, ..self }
+    }
+
+    //Запись текущей игры. Ее можно сохранить и повторить с помощью ReplayPlayer
+    pub fn get_replay(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    //Снимок игры для отрисовки
+    pub fn snapshot(&self) -> GameSnapshot {
+        let players = 0..self.game.players.len();
+        GameSnapshot {
+            phase: self.get_phase(),
+            points: self.get_state(),
+            topology: self.get_topology(),
+            scores: self.get_scores(),
+            max_score: self.get_max_score(),
+            tick_interval: self.get_tick_interval(),
+            bots: players.clone().map(|i| self.is_bot(i)).collect(),
+            autopilots: players.map(|i| self.is_autopilot(i)).collect(),
+        }
+    }
+
+    //Текущий этап игры чтобы представление показало нужный экран
+    pub fn get_phase(&self) -> Phase {
+        self.game.phase.clone()
+    }
+
+    pub fn get_max_score(&self) -> usize {
+        self.game.max_score
+    }
+
+    //Счет первого игрока
+    pub fn get_score(&self) -> usize {
+        self.game.players[0].score
+    }
+
+    //Управляет ли змейкой игрока компьютер
+    pub fn is_bot(&self, player: usize) -> bool {
+        self.game.players.get(player).is_some_and(|p| p.bot.is_some())
+    }
+
+    //Ведет ли змейку игрока автопилот
+    pub fn is_autopilot(&self, player: usize) -> bool {
+        self.game.players.get(player).is_some_and(|p| p.bot == Some(AiLevel::Autopilot))
+    }
+
+    //Счет каждого игрока по порядку номеров
+    pub fn get_scores(&self) -> Vec<usize> {
+        self.game.players.iter().map(|p| p.score).collect()
+    }
+}
@@ -0,0 +1,473 @@
+//Data Access Layer ----------------------------------------------------------------
+
+use bincode::Options;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::error::Error;
+use entities::{Coord, Food, FoodConfig, FoodKind, Frame, Point, Topology};
+use grid::Grid;
+use level::Level;
+use controller::GameSnapshot;
+use logic::Game;
+use picture::{replay_frames, snapshot_svg, AnimationConfig, Picture, Theme};
+use replay::Replay;
+
+//Генератор случайных чисел игры. Pcg32 дает одну и ту же последовательность
+//для одного и того же зерна на любой платформе поэтому игру можно воспроизвести.
+pub type GameRng = rand_pcg::Pcg32;
+
+//Сколько случайных клеток пробуем прежде чем перебирать все свободные
+const RANDOM_ATTEMPTS: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+//Структура для создания новой еды для змейки
+pub struct FoodGenerator {
+    pub frame: Frame,
+    pub topology: Topology,
+    pub walls: Vec<Point>,
+    //Еда заданная уровнем. Выдается по порядку до случайной
+    pub fixed_food: std::collections::VecDeque<Point>,
+    //Вся еда уровня. Нужна чтобы в новой игре она снова появилась первой
+    pub level_food: Vec<Point>,
+    pub config: FoodConfig,
+    rng: GameRng,
+}
+
+impl Default for FoodGenerator {
+    fn default() -> FoodGenerator {
+        FoodGenerator::new(Frame::default(), 0)
+    }
+}
+
+impl FoodGenerator {
+    //Создает генератор который будет выдавать одинаковую еду для одинакового зерна
+    pub fn new(frame: Frame, seed: u64) -> FoodGenerator {
+        FoodGenerator::from_rng(frame, GameRng::seed_from_u64(seed))
+    }
+
+    //Создает генератор с генератором случайных чисел переданным снаружи
+    pub fn from_rng(frame: Frame, rng: GameRng) -> FoodGenerator {
+        FoodGenerator {
+            frame,
+            topology: Topology::default(),
+            walls: Vec::new(),
+            fixed_food: std::collections::VecDeque::new(),
+            level_food: Vec::new(),
+            config: FoodConfig::default(),
+            rng,
+        }
+    }
+
+    //Задает форму поля. На поле без стен еда может появиться и на границе фрейма
+    pub fn with_topology(self, topology: Topology) -> FoodGenerator {
+        FoodGenerator { topology, ..self }
+    }
+
+    //Задает стены внутри фрейма на которых еда появляться не должна
+    pub fn with_walls(self, walls: Vec<Point>) -> FoodGenerator {
+        FoodGenerator { walls, ..self }
+    }
+
+    //Задает еду которая будет выдана раньше случайной
+    pub fn with_fixed_food(self, food: Vec<Point>) -> FoodGenerator {
+        FoodGenerator { fixed_food: food.iter().cloned().collect(), level_food: food, ..self }
+    }
+
+    //Начинает выдавать еду заново с заданного зерна, начиная с еды уровня
+    pub fn reseed(self, seed: u64) -> FoodGenerator {
+        let fixed_food = self.level_food.iter().cloned().collect();
+        FoodGenerator { rng: GameRng::seed_from_u64(seed), fixed_food, ..self }
+    }
+
+    //Случайное зерно для следующей игры
+    pub fn next_seed(self) -> (FoodGenerator, u64) {
+        let mut generator = self;
+        let seed = generator.rng.gen();
+        (generator, seed)
+    }
+
+    //Задает сколько еды лежит на поле и как часто появляется каждый ее вид
+    pub fn with_config(self, config: FoodConfig) -> FoodGenerator {
+        FoodGenerator { config, ..self }
+    }
+
+    //Границы клеток по которым может ползать змейка. На поле со стенами граница фрейма не входит
+    pub fn bounds(&self) -> Option<(Coord, Coord, Coord, Coord)> {
+        let frame = &self.frame;
+        let (min_x, min_y, max_x, max_y) = match self.topology {
+            Topology::Walled => (
+                frame.min_x.saturating_add(1),
+                frame.min_y.saturating_add(1),
+                frame.max_x.saturating_sub(1),
+                frame.max_y.saturating_sub(1),
+            ),
+            Topology::Wrapped => (frame.min_x, frame.min_y, frame.max_x, frame.max_y),
+        };
+        if min_x > max_x || min_y > max_y {
+            None
+        } else {
+            Some((min_x, min_y, max_x, max_y))
+        }
+    }
+
+    //Сколько всего клеток поля не занято стенами. Игра считает это один раз, когда собирает сетку
+    pub fn playable_cells(&self) -> usize {
+        match self.bounds() {
+            None => 0,
+            Some((min_x, min_y, max_x, max_y)) => {
+                let area = (max_x - min_x) as usize + 1;
+                let area = area * ((max_y - min_y) as usize + 1);
+                let walls: HashSet<&Point> = self.walls.iter()
+                    .filter(|w| w.x >= min_x && w.x <= max_x && w.y >= min_y && w.y <= max_y)
+                    .collect();
+                area - walls.len()
+            }
+        }
+    }
+
+    //Создает новую еду в случайном месте в пределах фрейма.
+    //Выбираем только из клеток на которых нет стен и которые не заняты змейкой или другой едой.
+    //Стены, змейки и еду знает сетка игры. Если свободных клеток не осталось то еды не будет
+    pub fn generate(self, grid: &Grid) -> (FoodGenerator, Option<Food>) {
+        let mut generator = self;
+        while let Some(point) = generator.fixed_food.pop_front() {
+            if grid.is_free(&point) {
+                return (generator, Some(Food { point, kind: FoodKind::Normal }));
+            }
+        }
+        let (min_x, min_y, max_x, max_y) = match generator.bounds() {
+            Some(bounds) => bounds,
+            None => return (generator, None),
+        };
+        //Пока поле почти свободно быстрее просто попробовать несколько случайных клеток
+        let mut point = None;
+        for _ in 0..RANDOM_ATTEMPTS {
+            let candidate = Point {
+                x: generator.rng.gen_range(min_x, max_x + 1),
+                y: generator.rng.gen_range(min_y, max_y + 1),
+            };
+            if grid.is_free(&candidate) {
+                point = Some(candidate);
+                break;
+            }
+        }
+        //Иначе выбираем из всех свободных клеток
+        if point.is_none() {
+            let mut cells = Vec::new();
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    let cell = Point { x, y };
+                    if grid.is_free(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+            if !cells.is_empty() {
+                let index = generator.rng.gen_range(0, cells.len());
+                point = Some(cells.swap_remove(index));
+            }
+        }
+        match point {
+            None => (generator, None),
+            Some(point) => {
+                let (generator, kind) = generator.generate_kind();
+                (generator, Some(Food { point, kind }))
+            }
+        }
+    }
+
+    //Выбираем вид еды с учетом веса каждого вида
+    fn generate_kind(self) -> (FoodGenerator, FoodKind) {
+        let mut generator = self;
+        let weights = [
+            (FoodKind::Normal, generator.config.normal_weight),
+            (FoodKind::Bonus, generator.config.bonus_weight),
+            (FoodKind::Poison, generator.config.poison_weight),
+            (FoodKind::Golden, generator.config.golden_weight),
+        ];
+        let total: u32 = weights.iter().map(|w| w.1).sum();
+        if total == 0 {
+            return (generator, FoodKind::Normal);
+        }
+        let mut roll = generator.rng.gen_range(0, total);
+        for (kind, weight) in weights.iter() {
+            if roll < *weight {
+                return (generator, *kind);
+            }
+            roll -= weight;
+        }
+        (generator, FoodKind::Normal)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+//Хранит максимальный счет игры в файле. Сама игра файлов не трогает:
+//рекорд читают и сохраняют только представления
+pub struct ScoreRepository {
+    score: usize
+}
+
+impl ScoreRepository {
+    //Статический метод для сохранения текущего счета в файле
+    // Result это перечесление которое может хранить в себе либо ошибку либо результат вычислений
+    pub fn save(value: usize) -> Result<(), Box<dyn Error>> {
+        use std::fs::File;
+        use std::io::Write;
+        let score = ScoreRepository { score: value };
+        //Сериализуем структуру в массив байтов с помощью библиотеки bincode
+        let bytes: Vec<u8> = bincode::serialize(&score)?;
+        //Создаем новый файл или если он уже сушествует то перезаписываем его.
+        let mut file = File::create("./score.data")?;
+        match file.write_all(&bytes) {
+            Ok(t) => Ok(t),
+            //Error это трейт а у трейт нет точного размера во время компиляции поэтому
+            // нам надо обернуть значнеие в Box и в результате мы работает с указателем на
+            //кучу в памяти где лежит наш обьект а не с самим обьектом а у указателя есть определенный размер
+            // известный во время компиляции
+            Err(e) => Err(Box::new(e))
+        }
+    }
+
+    //Загружаем сохраненный результат из файла
+    pub fn load() -> Result<usize, Box<dyn Error>> {
+        use std::fs::File;
+        let file = File::open("./score.data")?;
+        let data: ScoreRepository = bincode::deserialize_from(file)?;
+        Ok(data.score)
+    }
+}
+
+//Загружает уровни из текстовых файлов
+pub struct LevelRepository;
+
+impl LevelRepository {
+    //Загружаем уровень из файла
+    pub fn load(path: &str) -> Result<Level, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        match Level::parse(&text) {
+            Ok(level) => Ok(level),
+            Err(e) => Err(format!("{}: {}", path, e).into())
+        }
+    }
+
+    //Имена всех уровней в папке. Уровень это файл с расширением .txt
+    pub fn list(directory: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("txt") {
+                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+//Настройки bincode для файлов записей и сохранений.
+//Числа пишутся переменной длины, поэтому номера шагов и координаты занимают по байту-два
+fn compact() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+//Версия формата файла записи. Меняется когда меняется структура Replay
+const REPLAY_VERSION: u32 = 1;
+
+//Сохраняет и загружает записи игр
+pub struct ReplayRepository;
+
+impl ReplayRepository {
+    //Сохраняем запись в файл. Сначала идет версия формата, потом сама запись
+    pub fn save(path: &str, replay: &Replay) -> Result<(), Box<dyn Error>> {
+        let mut bytes = compact().serialize(&REPLAY_VERSION)?;
+        bytes.extend(compact().serialize(replay)?);
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    //Загружаем запись из файла. Запись другой версии не читаем чтобы не повторить ее неправильно
+    pub fn load(path: &str) -> Result<Replay, Box<dyn Error>> {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let version: u32 = compact().deserialize_from(&mut reader)?;
+        if version != REPLAY_VERSION {
+            return Err(format!("{}: unsupported replay version {}", path, version).into());
+        }
+        Ok(compact().deserialize_from(&mut reader)?)
+    }
+}
+
+//Версия формата сохраненной игры. Меняется когда меняется структура сохранения.
+//Версия 1 хранила только игру, версия 2 хранит еще и запись игры
+const SAVE_VERSION: u32 = 2;
+
+//Сохраняет недоигранную игру при выходе чтобы ее можно было продолжить
+pub struct SaveRepository;
+
+impl SaveRepository {
+    //Сохраняем игру и ее запись в файл. Сначала идет версия формата, потом игра и запись.
+    //Без записи продолженную игру нельзя было бы повторить
+    pub fn save(path: &str, game: &Game, recording: Option<&Replay>) -> Result<(), Box<dyn Error>> {
+        let mut bytes = compact().serialize(&SAVE_VERSION)?;
+        bytes.extend(compact().serialize(&(game, recording))?);
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    //Загружаем игру и ее запись из файла и заново собираем сетку игры.
+    //В сохранении версии 1 записи нет, такая игра продолжается без записи
+    pub fn load(path: &str) -> Result<(Game, Option<Replay>), Box<dyn Error>> {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let version: u32 = compact()
+            .deserialize_from(&mut reader)
+            .map_err(|e| format!("{}: not a saved game: {}", path, e))?;
+        let damaged = |e: bincode::Error| format!("{}: damaged save of version {}: {}", path, version, e);
+        let (game, recording): (Game, Option<Replay>) = match version {
+            1 => (compact().deserialize_from(&mut reader).map_err(damaged)?, None),
+            SAVE_VERSION => compact().deserialize_from(&mut reader).map_err(damaged)?,
+            _ => {
+                let message = format!("{}: unknown save version {}, this game reads versions 1 to {}", path, version, SAVE_VERSION);
+                return Err(message.into());
+            }
+        };
+        Ok((game.with_grid(), recording))
+    }
+
+    //Удаляем сохранение когда игра продолжена или начата новая
+    pub fn remove(path: &str) -> Result<(), Box<dyn Error>> {
+        match std::fs::remove_file(path) {
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
+
+//Расширение файла маленькими буквами
+fn extension(path: &str) -> Option<String> {
+    std::path::Path::new(path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase())
+}
+
+//Сохраняет картинки снимков игры
+pub struct PictureRepository;
+
+impl PictureRepository {
+    //Сохраняем снимок в SVG или PNG, формат выбирается по расширению файла
+    pub fn save(path: &str, snapshot: &GameSnapshot, cell: u32, theme: &Theme) -> Result<(), Box<dyn Error>> {
+        match extension(path).as_deref() {
+            Some("svg") => std::fs::write(path, snapshot_svg(snapshot, cell, theme))?,
+            Some("png") => PictureRepository::save_png(path, &Picture::from_snapshot(snapshot, cell, theme))?,
+            _ => return Err(format!("{}: expected .svg or .png", path).into()),
+        }
+        Ok(())
+    }
+
+    pub fn save_png(path: &str, picture: &Picture) -> Result<(), Box<dyn Error>> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, picture.width, picture.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&picture.pixels)?;
+        Ok(())
+    }
+
+    //Читаем PNG обратно в память. Нужно чтобы сравнивать картинки с эталонами
+    pub fn load_png(path: &str) -> Result<Picture, Box<dyn Error>> {
+        let mut decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => buffer[..info.buffer_size()].to_vec(),
+            png::ColorType::Rgba => buffer[..info.buffer_size()].chunks(4).flat_map(|p| p[..3].to_vec()).collect(),
+            other => return Err(format!("{}: unsupported color type {:?}", path, other).into()),
+        };
+        Ok(Picture { width: info.width, height: info.height, pixels })
+    }
+}
+
+//Сохраняет записи игр в виде анимации
+pub struct AnimationRepository;
+
+impl AnimationRepository {
+    //Рисуем запись и сохраняем ее в GIF или APNG, формат выбирается по расширению файла.
+    //Кадры рисуются и пишутся по одному. Возвращает число кадров
+    pub fn save(path: &str, replay: &Replay, config: &AnimationConfig) -> Result<usize, Box<dyn Error>> {
+        let frames = replay_frames(replay, config)?;
+        match extension(path).as_deref() {
+            Some("gif") => AnimationRepository::save_gif(path, frames, config.fps),
+            Some("png") | Some("apng") => AnimationRepository::save_apng(path, frames, config.fps),
+            _ => Err(format!("{}: expected .gif, .png or .apng", path).into()),
+        }
+    }
+
+    //Задержка в GIF задается в сотых долях секунды. Когда 100 не делится на fps
+    //остаток переносится на следующие кадры, чтобы анимация шла с заданной скоростью
+    pub fn save_gif<I: IntoIterator<Item = Picture>>(path: &str, frames: I, fps: u32) -> Result<usize, Box<dyn Error>> {
+        let mut frames = frames.into_iter();
+        let first = frames.next().ok_or("no frames to save")?;
+        if first.width > u16::MAX as u32 || first.height > u16::MAX as u32 {
+            return Err(format!("{}: {}x{} is too large for GIF", path, first.width, first.height).into());
+        }
+        let (width, height) = (first.width as u16, first.height as u16);
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        let fps = fps.max(1) as u64;
+        let (mut count, mut shown) = (0, 0);
+        for picture in std::iter::once(first).chain(frames) {
+            count += 1;
+            let delay = (count * 100 / fps).saturating_sub(shown).max(1);
+            shown += delay;
+            let mut frame = gif::Frame::from_rgb_speed(width, height, &picture.pixels, 10);
+            frame.delay = delay.min(u16::MAX as u64) as u16;
+            encoder.write_frame(&frame)?;
+        }
+        Ok(count as usize)
+    }
+
+    //Число кадров пишется в заголовок APNG до самих кадров. Чтобы не проигрывать запись дважды
+    //в заголовок сначала пишем наибольшее число, а когда кадры кончились исправляем его на настоящее
+    pub fn save_apng<I: IntoIterator<Item = Picture>>(path: &str, frames: I, fps: u32) -> Result<usize, Box<dyn Error>> {
+        let mut frames = frames.into_iter();
+        let first = frames.next().ok_or("no frames to save")?;
+        //Файл открываем и на чтение: в конце из него читается заголовок
+        let file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        let mut file = std::io::BufWriter::new(file);
+        let mut encoder = png::Encoder::new(&mut file, first.width, first.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(u32::MAX, 0)?;
+        encoder.set_frame_delay(1, fps.clamp(1, u16::MAX as u32) as u16)?;
+        let mut writer = encoder.write_header()?;
+        let mut written = 0;
+        for picture in std::iter::once(first).chain(frames) {
+            writer.write_image_data(&picture.pixels)?;
+            written += 1;
+        }
+        writer.finish()?;
+        let mut file = file.into_inner().map_err(|e| e.into_error())?;
+        AnimationRepository::set_apng_frames(&mut file, written as u32)?;
+        Ok(written)
+    }
+
+    //Переписываем число кадров в чанке acTL и его контрольную сумму.
+    //Чанк идет сразу за IHDR: 8 байт подписи PNG и 25 байт IHDR
+    fn set_apng_frames(file: &mut std::fs::File, frames: u32) -> Result<(), Box<dyn Error>> {
+        use std::io::{Read, Seek, SeekFrom, Write};
+        const ACTL: u64 = 33;
+        let mut chunk = [0u8; 20];
+        file.seek(SeekFrom::Start(ACTL))?;
+        file.read_exact(&mut chunk)?;
+        if &chunk[4..8] != b"acTL" {
+            return Err("APNG header without acTL chunk".into());
+        }
+        chunk[8..12].copy_from_slice(&frames.to_be_bytes());
+        let crc = crc32fast::hash(&chunk[4..16]);
+        chunk[16..20].copy_from_slice(&crc.to_be_bytes());
+        file.seek(SeekFrom::Start(ACTL))?;
+        file.write_all(&chunk)?;
+        Ok(())
+    }
+}
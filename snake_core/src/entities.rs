@@ -0,0 +1,409 @@
+//Entities ------------------------------------------------------------------
+
+/*
+Это макросы. Они генерируют какой нибудь код автоматически.
+В нашем конкретном случае:
+Debug - Создаст код который позволить выводить нашу структуру в терминал
+Clone - Создаст код который будет копировать нашу структуру т. е. у нашей структуры появиться метод clone()
+Eq и PartialEq позволять сравнивать наши Point с помошью оператора ==
+*/
+//Тип координат на поле. u16 позволяет играть на полях размером в тысячи клеток
+pub type Coord = u16;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
+//Обьявление структуры с двумя полями. Она будет играть роль точки
+pub struct Point {
+    pub x: Coord,
+    pub y: Coord,
+}
+
+//Методы нашей структуры
+impl Point {
+    // Можно было использовать просто оператор == В общем, это метот который проверяет пересекаються ли наши точки
+    pub fn intersects(&self, point: &Point) -> bool {
+        self.x == point.x && self.y == point.y
+    }
+
+    //Соседняя точка в заданном направлении.
+    //На поле без стен точка на краю фрейма переходит на противоположную сторону.
+    //На краю диапазона координат точка остается на месте вместо переполнения
+    pub fn step(&self, direction: &Direction, frame: &Frame, topology: Topology) -> Point {
+        let wrap = topology == Topology::Wrapped;
+        match *direction {
+            Direction::Right => Point {
+                x: if wrap && self.x == frame.max_x { frame.min_x } else { self.x.saturating_add(1) },
+                y: self.y,
+            },
+            Direction::Left => Point {
+                x: if wrap && self.x == frame.min_x { frame.max_x } else { self.x.saturating_sub(1) },
+                y: self.y,
+            },
+            Direction::Top => Point {
+                x: self.x,
+                y: if wrap && self.y == frame.min_y { frame.max_y } else { self.y.saturating_sub(1) },
+            },
+            Direction::Bottom => Point {
+                x: self.x,
+                y: if wrap && self.y == frame.max_y { frame.min_y } else { self.y.saturating_add(1) },
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Эта структура будет хранить обьектное представление границ фрейма в пределах которого будет двигаться наша змейка
+pub struct Frame {
+    pub min_x: Coord,
+    pub min_y: Coord,
+    pub max_x: Coord,
+    pub max_y: Coord,
+}
+
+impl Frame {
+    pub fn intersects(&self, point: &Point) -> bool {
+        point.x == self.min_x
+            || point.y == self.min_y
+            || point.x == self.max_x
+            || point.y == self.max_y
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Вид еды. От него зависит что случится со змейкой когда она ее съест
+pub enum FoodKind {
+    //Одно очко и одна новая точка
+    #[default]
+    Normal,
+    //Много очков за раз
+    Bonus,
+    //Укорачивает змейку
+    Poison,
+    //Сразу удлиняет змейку на несколько точек
+    Golden,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Еда на поле
+pub struct Food {
+    pub point: Point,
+    pub kind: FoodKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Настройки еды: сколько ее лежит на поле, как часто появляется каждый вид и какой у него эффект
+pub struct FoodConfig {
+    pub count: usize,
+    pub normal_weight: u32,
+    pub bonus_weight: u32,
+    pub poison_weight: u32,
+    pub golden_weight: u32,
+    pub bonus_points: usize,
+    pub poison_shrink: usize,
+    pub golden_growth: usize,
+}
+
+//По умолчанию на поле одна обычная еда как в первой версии игры
+impl Default for FoodConfig {
+    fn default() -> FoodConfig {
+        FoodConfig {
+            count: 1,
+            normal_weight: 1,
+            bonus_weight: 0,
+            poison_weight: 0,
+            golden_weight: 0,
+            bonus_points: 5,
+            poison_shrink: 2,
+            golden_growth: 3,
+        }
+    }
+}
+
+impl FoodConfig {
+    //Несколько видов еды одновременно. Обычная еда встречается чаще остальных
+    pub fn mixed(count: usize) -> FoodConfig {
+        FoodConfig {
+            count,
+            normal_weight: 6,
+            bonus_weight: 2,
+            poison_weight: 1,
+            golden_weight: 1,
+            ..FoodConfig::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+//Как часто двигается змейка. Интервал между шагами в секундах
+pub enum SpeedCurve {
+    //Всегда одна и та же скорость
+    Fixed { interval: f32 },
+    //С каждой новой точкой змейки интервал уменьшается на step но не меньше min
+    ByLength { start: f32, step: f32, min: f32 },
+    //С каждым очком интервал уменьшается на step но не меньше min
+    ByScore { start: f32, step: f32, min: f32 },
+}
+
+//По умолчанию скорость как в первой версии игры
+impl Default for SpeedCurve {
+    fn default() -> SpeedCurve {
+        SpeedCurve::Fixed { interval: 0.030 }
+    }
+}
+
+impl SpeedCurve {
+    //Интервал между шагами змейки для текущей длины и счета
+    pub fn interval(&self, length: usize, score: usize) -> f32 {
+        match *self {
+            SpeedCurve::Fixed { interval } => interval,
+            SpeedCurve::ByLength { start, step, min } => (start - step * length as f32).max(min),
+            SpeedCurve::ByScore { start, step, min } => (start - step * score as f32).max(min),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+//Готовые уровни сложности
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    //Очень быстрая игра как в первой версии
+    Classic,
+}
+
+impl Difficulty {
+    pub fn speed_curve(self) -> SpeedCurve {
+        match self {
+            Difficulty::Easy => SpeedCurve::ByScore { start: 0.20, step: 0.005, min: 0.08 },
+            Difficulty::Normal => SpeedCurve::ByLength { start: 0.12, step: 0.002, min: 0.05 },
+            Difficulty::Hard => SpeedCurve::ByLength { start: 0.08, step: 0.002, min: 0.03 },
+            Difficulty::Classic => SpeedCurve::default(),
+        }
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            "classic" => Ok(Difficulty::Classic),
+            _ => Err(format!("unknown difficulty '{}', expected easy, normal, hard or classic", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Форма игрового поля
+pub enum Topology {
+    //Граница фрейма это стена и змейка погибает при столкновении с ней
+    #[default]
+    Walled,
+    //Стен нет. Весь фрейм включая границу это поле, а выйдя за одну сторону
+    //змейка появляется с противоположной стороны
+    Wrapped,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Обьявление перечисления с 4 вариантами
+//Оно будет отвечать за то куда в данный момент повернута голова змейки
+pub enum Direction {
+    Left,
+    //Резализация трейта Default (в других языках это еще называеться интерфейс)
+    //для нашего перечесления. Обьект реализующий этот трейт способен иметь значение по умолчанию.
+    #[default]
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Direction {
+    //Противоположное направление
+    pub fn opposite(&self) -> Direction {
+        match *self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Top => Direction::Bottom,
+            Direction::Bottom => Direction::Top,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Собственно наша змейка
+pub struct Snake {
+    pub direction: Direction,
+    pub points: std::collections::VecDeque<Point>,
+    pub start_x: Coord,
+    pub start_y: Coord,
+    //Начальное положение и направление в которые змейка возвращается после гибели
+    pub start_direction: Direction,
+    pub start_points: std::collections::VecDeque<Point>,
+}
+
+impl Snake {
+    //Статический метод конструктор для инициализации нового экземлпяра нашей змейки
+    pub fn new(x: Coord, y: Coord) -> Snake {
+        let mut points = std::collections::VecDeque::new();
+        for i in 0..3 {
+            points.push_front(Point { x: x.saturating_add(i), y: y.saturating_add(i) });
+        }
+        Snake {
+            direction: Direction::default(),
+            points: points.clone(),
+            start_x: x,
+            start_y: y,
+            start_direction: Direction::default(),
+            start_points: points,
+        }
+    }
+
+    //Создает змейку с головой в заданной точке смотрящую в заданном направлении.
+    //Тело из двух точек лежит позади головы
+    pub fn spawn(head: Point, direction: Direction) -> Snake {
+        let mut points = std::collections::VecDeque::new();
+        for i in 0..3 {
+            let point = match direction {
+                Direction::Right => Point { x: head.x.saturating_sub(i), y: head.y },
+                Direction::Left => Point { x: head.x.saturating_add(i), y: head.y },
+                Direction::Top => Point { x: head.x, y: head.y.saturating_add(i) },
+                Direction::Bottom => Point { x: head.x, y: head.y.saturating_sub(i) },
+            };
+            points.push_back(point);
+        }
+        Snake {
+            direction: direction.clone(),
+            points: points.clone(),
+            start_x: head.x,
+            start_y: head.y,
+            start_direction: direction,
+            start_points: points,
+        }
+    }
+    //Змейка отраженная относительно центра фрейма и смотрящая в обратную сторону.
+    //Так второй игрок начинает в таком же положении как и первый
+    pub fn mirrored(&self, frame: &Frame) -> Snake {
+        let mirror = |p: &Point| Point {
+            x: (frame.min_x + frame.max_x).saturating_sub(p.x),
+            y: (frame.min_y + frame.max_y).saturating_sub(p.y),
+        };
+        let points: std::collections::VecDeque<Point> = self.start_points.iter().map(mirror).collect();
+        let head = points.front().cloned().unwrap_or_default();
+        Snake {
+            direction: self.start_direction.opposite(),
+            points: points.clone(),
+            start_x: head.x,
+            start_y: head.y,
+            start_direction: self.start_direction.opposite(),
+            start_points: points,
+        }
+    }
+
+    //Увеличивает длину нашей змейки на одну точку
+    pub fn grow(self) -> Snake {
+        let mut points = self.points;
+        if let Some(tail) = points.pop_back() {
+            points.push_back(Point { x: tail.x, y: tail.y });
+            points.push_back(tail);
+        }
+        Snake { points, ..self }
+    }
+
+    //Укорачивает змейку с хвоста. Голова всегда остается
+    pub fn shrink(self, count: usize) -> Snake {
+        let mut points = self.points;
+        for _ in 0..count {
+            if points.len() > 1 {
+                points.pop_back();
+            }
+        }
+        Snake { points, ..self }
+    }
+
+    //Сбрасывает нашу змейку в начальное состояние
+    pub fn reset(self) -> Snake {
+        Snake {
+            direction: self.start_direction.clone(),
+            points: self.start_points.clone(),
+            ..self
+        }
+    }
+
+    //Поворачивает голову змейки в нужном нам направлении
+    pub fn turn(self, direction: Direction) -> Snake {
+        Snake { direction, ..self }
+    }
+
+    //Если голова змейки достает до еды то увеличивает длину змейки на один и возврашает информацию о том была ли еда съедена
+    pub fn try_eat(self, point: &Point) -> (Snake, bool) {
+        let head = self.head();
+        if head.intersects(point) {
+            return (self.grow(), true);
+        }
+        (self, false)
+    }
+
+    //Столкнулась ли голова змейки с фреймом
+    pub fn intersects_frame(&self, frame: &Frame) -> bool {
+        frame.intersects(&self.head())
+    }
+
+    //Столкнулась ли голова змейки со стеной внутри фрейма
+    pub fn intersects_walls(&self, walls: &[Point]) -> bool {
+        let head = self.head();
+        walls.iter().any(|w| w.intersects(&head))
+    }
+
+    //Столкнулась ли голова змейки с остальной частью
+    pub fn intersects_tail(&self) -> bool {
+        let head = self.head();
+        self.points.iter().filter(|p| head.intersects(p)).count() > 1
+    }
+
+    //Если голова змейки столкнулась с фреймом то возвращает змейку в начальное состояние
+    pub fn try_intersect_frame(self, frame: &Frame) -> Snake {
+        if self.intersects_frame(frame) {
+            return self.reset();
+        }
+        self
+    }
+
+    //Если голова змейки столкнулась со стеной внутри фрейма то возвращает змейку в начальное состояние
+    pub fn try_intersect_walls(self, walls: &[Point]) -> Snake {
+        if self.intersects_walls(walls) {
+            return self.reset();
+        }
+        self
+    }
+
+    //Если голова змейки столкнулась с остальной частью то возвращает змейку в начальное состояние.
+    pub fn try_intersect_tail(self) -> Snake {
+        if self.intersects_tail() {
+            return self.reset();
+        }
+        self
+    }
+
+    //Дает голову змейки
+    pub fn head(&self) -> Point {
+        self.points.front().unwrap().clone()
+    }
+
+    //Перемещает змейку на одну точку в том направление куда в данный момент смотрит голова змейки.
+    //На поле без стен голова переходит через край фрейма на противоположную сторону.
+    //Если дальше двигаться некуда из за предела координат голова остается на месте и попадает в шею змейки
+    pub fn move_snake(self, frame: &Frame, topology: Topology) -> Snake {
+        let next = self.head().step(&self.direction, frame, topology);
+        let mut points = self.points;
+        if let Some(mut tail) = points.pop_back() {
+            tail.x = next.x;
+            tail.y = next.y;
+            points.push_front(tail);
+        }
+        Snake { points, ..self }
+    }
+}
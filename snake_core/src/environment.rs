@@ -0,0 +1,283 @@
+//Application Layer. Среда для обучения с подкреплением ------------------------------
+
+/*
+Обертка над игрой в стиле gym: reset(seed) начинает новую игру и возвращает наблюдение,
+step(action) делает ровно один шаг змейкой и возвращает наблюдение, награду, признак конца игры
+и подробности. Время в среде не идет, каждый вызов step это один шаг игры.
+*/
+
+use std::cmp::Ordering;
+use ai::AiLevel;
+use controller::GameController;
+use entities::{Direction, FoodConfig, Point, Topology};
+use level::Level;
+use logic::{DeathCause, Game, Outcome, Phase};
+
+//Действия агента по порядку номеров
+pub const ACTIONS: [Direction; 4] = [Direction::Right, Direction::Bottom, Direction::Left, Direction::Top];
+
+//Каналы сетки наблюдения по порядку
+pub const CHANNELS: usize = 4;
+pub const HEAD_CHANNEL: usize = 0;
+pub const BODY_CHANNEL: usize = 1;
+pub const FOOD_CHANNEL: usize = 2;
+pub const WALL_CHANNEL: usize = 3;
+
+//Длина вектора признаков: голова (2), направление (4), опасность в каждую сторону (4),
+//смещение до ближайшей еды (2) и длина змейки (1)
+pub const FEATURES: usize = 13;
+
+//Зерно игры до первого reset
+const DEFAULT_SEED: u64 = 0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+//Сколько агент получает за каждое событие. Отрицательные значения это штраф
+pub struct RewardConfig {
+    //За каждое очко счета
+    pub food: f32,
+    //За гибель змейки
+    pub death: f32,
+    //За каждый шаг. Небольшой штраф заставляет не ходить кругами
+    pub step: f32,
+    //За шаг к ближайшей еде. За шаг от нее столько же штрафа
+    pub approach: f32,
+    //За заполнение всего поля или победу над остальными змейками
+    pub win: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> RewardConfig {
+        RewardConfig { food: 1.0, death: -1.0, step: 0.0, approach: 0.0, win: 10.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+//Что видит агент
+pub struct Observation {
+    //Сетка каналов размером CHANNELS * height * width.
+    //Клетка (x, y) канала c лежит по индексу (c * height + y) * width + x, y и x отсчитываются от угла фрейма
+    pub grid: Vec<f32>,
+    pub width: usize,
+    pub height: usize,
+    //Короткий вектор признаков длиной FEATURES
+    pub features: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+//Подробности шага которые не входят в награду
+pub struct StepInfo {
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    pub death: Option<DeathCause>,
+    //Игра остановлена из-за лимита шагов, а не закончилась сама
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+//Результат одного шага
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+    pub info: StepInfo,
+}
+
+#[derive(Debug, Clone)]
+//Среда с одной игрой. Агент управляет змейкой первого игрока
+pub struct Environment {
+    level: Level,
+    topology: Topology,
+    food: FoodConfig,
+    bots: usize,
+    ai: AiLevel,
+    rewards: RewardConfig,
+    max_ticks: u64,
+    pub game: Game,
+}
+
+impl Environment {
+    //Среда на заданном уровне. Игра сразу готова к шагам с зерном по умолчанию,
+    //reset начинает ее заново с нужным зерном
+    pub fn new(level: Level) -> Environment {
+        Environment {
+            level,
+            topology: Topology::default(),
+            food: FoodConfig::default(),
+            bots: 0,
+            ai: AiLevel::default(),
+            rewards: RewardConfig::default(),
+            max_ticks: u64::MAX,
+            game: Game::default(),
+        }
+        .start(DEFAULT_SEED)
+    }
+
+    pub fn with_topology(self, topology: Topology) -> Environment {
+        Environment { topology, ..self }.start(DEFAULT_SEED)
+    }
+
+    pub fn with_food_config(self, food: FoodConfig) -> Environment {
+        Environment { food, ..self }.start(DEFAULT_SEED)
+    }
+
+    //Добавляет соперников под управлением компьютера
+    pub fn with_bots(self, bots: usize, ai: AiLevel) -> Environment {
+        Environment { bots, ai, ..self }.start(DEFAULT_SEED)
+    }
+
+    pub fn with_rewards(self, rewards: RewardConfig) -> Environment {
+        Environment { rewards, ..self }
+    }
+
+    //После стольких шагов игра останавливается и done становится true
+    pub fn with_max_ticks(self, max_ticks: u64) -> Environment {
+        Environment { max_ticks, ..self }
+    }
+
+    //Начинаем новую игру. Одинаковое зерно и одинаковые действия дают одинаковую игру
+    pub fn reset(self, seed: u64) -> (Environment, Observation) {
+        let environment = self.start(seed);
+        let observation = environment.observe();
+        (environment, observation)
+    }
+
+    //Собираем игру по настройкам среды
+    fn start(self, seed: u64) -> Environment {
+        let game = GameController::with_seed(&self.level, seed)
+            .with_topology(self.topology)
+            .with_food_config(self.food.clone())
+            .with_bots(self.bots, self.ai)
+            .game
+            .with_phase(Phase::Playing);
+        Environment { game, ..self }
+    }
+
+    //Поворачиваем змейку и делаем ровно один шаг игры.
+    //Поворот назад в шею игра отбрасывает, тогда змейка едет прямо.
+    //После конца игры шаг ничего не меняет: то же наблюдение, награда 0 и done
+    pub fn step(self, action: Direction) -> (Environment, Step) {
+        let finished = self.game.phase != Phase::Playing;
+        if finished || self.game.ticks >= self.max_ticks {
+            let step = Step { observation: self.observe(), reward: 0.0, done: true, info: self.info(!finished) };
+            return (self, step);
+        }
+        let score = self.game.players[0].score;
+        let distance = self.food_distance();
+        let game = self.game.handle_input(0, action).advance();
+        let environment = Environment { game, ..self };
+        let player = &environment.game.players[0];
+        let rewards = &environment.rewards;
+        let points = player.score.saturating_sub(score);
+        let mut reward = rewards.step + points as f32 * rewards.food;
+        //Когда еда съедена ближайшей становится другая еда, поэтому сравнивать расстояния нельзя
+        if let (0, Some(before), Some(after)) = (points, distance, environment.food_distance()) {
+            reward += match after.cmp(&before) {
+                Ordering::Less => rewards.approach,
+                Ordering::Greater => -rewards.approach,
+                Ordering::Equal => 0.0,
+            };
+        }
+        let finished = environment.game.phase != Phase::Playing;
+        if player.death.is_some() {
+            reward += rewards.death;
+        } else if let Phase::GameOver(ref summary) = environment.game.phase {
+            match summary.outcome {
+                Outcome::BoardCleared | Outcome::Won(0) => reward += rewards.win,
+                _ => {}
+            }
+        }
+        let truncated = !finished && environment.game.ticks >= environment.max_ticks;
+        let step = Step { observation: environment.observe(), reward, done: finished || truncated, info: environment.info(truncated) };
+        (environment, step)
+    }
+
+    fn info(&self, truncated: bool) -> StepInfo {
+        let player = &self.game.players[0];
+        StepInfo {
+            score: player.score,
+            length: player.snake.points.len(),
+            ticks: self.game.ticks,
+            death: player.death,
+            truncated,
+        }
+    }
+
+    //Манхэттенское расстояние от головы до ближайшей еды
+    fn food_distance(&self) -> Option<usize> {
+        let head = self.game.players[0].snake.head();
+        self.game.foods.iter()
+            .map(|f| (f.point.x as isize - head.x as isize).unsigned_abs() + (f.point.y as isize - head.y as isize).unsigned_abs())
+            .min()
+    }
+
+    //Наблюдение для текущего состояния игры
+    pub fn observe(&self) -> Observation {
+        let game = &self.game;
+        let frame = &game.frame;
+        let width = (frame.max_x - frame.min_x) as usize + 1;
+        let height = (frame.max_y - frame.min_y) as usize + 1;
+        let mut grid = vec![0f32; CHANNELS * width * height];
+        let walled = game.topology == Topology::Walled;
+        let head = game.players[0].snake.head();
+        for y in frame.min_y..=frame.max_y {
+            for x in frame.min_x..=frame.max_x {
+                let point = Point { x, y };
+                let cell = game.grid.cell(&point);
+                let index = |channel: usize| (channel * height + (y - frame.min_y) as usize) * width + (x - frame.min_x) as usize;
+                if point == head {
+                    grid[index(HEAD_CHANNEL)] = 1.0;
+                } else if cell.snake > 0 {
+                    grid[index(BODY_CHANNEL)] = 1.0;
+                }
+                if cell.food {
+                    grid[index(FOOD_CHANNEL)] = 1.0;
+                }
+                if cell.wall || (walled && frame.intersects(&point)) {
+                    grid[index(WALL_CHANNEL)] = 1.0;
+                }
+            }
+        }
+        Observation { grid, width, height, features: self.features() }
+    }
+
+    fn features(&self) -> Vec<f32> {
+        let game = &self.game;
+        let frame = &game.frame;
+        let snake = &game.players[0].snake;
+        let head = snake.head();
+        let width = (frame.max_x - frame.min_x) as f32 + 1.0;
+        let height = (frame.max_y - frame.min_y) as f32 + 1.0;
+        let mut features = Vec::with_capacity(FEATURES);
+        features.push((head.x - frame.min_x) as f32 / width);
+        features.push((head.y - frame.min_y) as f32 / height);
+        for direction in ACTIONS.iter() {
+            features.push(if *direction == snake.direction { 1.0 } else { 0.0 });
+        }
+        //Опасно шагнуть туда где стена, фрейм или змейка
+        for direction in ACTIONS.iter() {
+            let next = head.step(direction, frame, game.topology);
+            let cell = game.grid.cell(&next);
+            let blocked = next == head
+                || cell.wall
+                || cell.snake > 0
+                || (game.topology == Topology::Walled && frame.intersects(&next));
+            features.push(if blocked { 1.0 } else { 0.0 });
+        }
+        let food = game.foods.iter().min_by_key(|f| {
+            (f.point.x as isize - head.x as isize).abs() + (f.point.y as isize - head.y as isize).abs()
+        });
+        match food {
+            Some(f) => {
+                features.push((f.point.x as f32 - head.x as f32) / width);
+                features.push((f.point.y as f32 - head.y as f32) / height);
+            }
+            None => {
+                features.push(0.0);
+                features.push(0.0);
+            }
+        }
+        features.push(snake.points.len() as f32 / (width * height));
+        features
+    }
+}
@@ -0,0 +1,182 @@
+//Presentation Layer. Общий цикл для любых представлений ---------------------------
+
+/*
+Представление состоит из трех частей: откуда берется игра (GameSource),
+кто ее рисует (Renderer) и откуда приходят команды (InputSource).
+Части не знают друг о друге, поэтому 3D окно, терминал, сетевую игру, записанные команды
+и тестовые заглушки можно сочетать в одном и том же цикле run.
+Обычно команды это команды игры. Представление может взять свой тип команд,
+если у него есть действия о которых игра не знает, например сохранить картинку кадра.
+*/
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::time::Instant;
+use controller::{GameController, GameSnapshot};
+use logic::{Command, DeathCause, Outcome, Phase};
+use network::RemoteGame;
+
+//Игра которую показывает представление: локальная, на сервере или повтор записи
+pub trait GameSource<C = Command> {
+    //Передаем команды игре и даем ей время
+    fn update(self, time_delta: f32, commands: Vec<C>) -> Self;
+    //Все что нужно нарисовать
+    fn snapshot(&self) -> GameSnapshot;
+    //Строка состояния которую знает только источник: номер игрока в сети, шаг повтора и так далее
+    fn status(&self) -> String {
+        String::new()
+    }
+}
+
+impl GameSource for GameController {
+    fn update(self, time_delta: f32, commands: Vec<Command>) -> GameController {
+        GameController::update(self, time_delta, commands)
+    }
+
+    fn snapshot(&self) -> GameSnapshot {
+        GameController::snapshot(self)
+    }
+}
+
+impl GameSource for RemoteGame {
+    //Сетевую игру двигает сервер, поэтому время не нужно
+    fn update(self, _: f32, commands: Vec<Command>) -> RemoteGame {
+        RemoteGame::update(self, commands)
+    }
+
+    fn snapshot(&self) -> GameSnapshot {
+        RemoteGame::snapshot(self).clone()
+    }
+
+    fn status(&self) -> String {
+        match (self.is_connected(), self.player()) {
+            (false, _) => "DISCONNECTED FROM THE SERVER".to_string(),
+            (true, Some(p)) => format!("YOU ARE P{}", p + 1),
+            (true, None) => "SPECTATOR".to_string(),
+        }
+    }
+}
+
+//Рисует кадр игры
+pub trait Renderer {
+    //Окно еще открыто. Окно может само обрабатывать события в этот момент
+    fn is_open(&mut self) -> bool {
+        true
+    }
+    fn render(&mut self, snapshot: &GameSnapshot, status: &str) -> Result<(), Box<dyn Error>>;
+}
+
+//Источник команд игрока: клавиатура, сеть, компьютер или сценарий теста
+pub trait InputSource<C = Command> {
+    //Команды с прошлого кадра по порядку
+    fn commands(&mut self) -> Vec<C>;
+    //Игрок хочет выйти
+    fn is_quit(&self) -> bool {
+        false
+    }
+}
+
+//Команды заданные заранее: на каждом кадре выдается следующий набор.
+//Когда наборы заканчиваются источник просит выйти
+#[derive(Debug, Clone)]
+pub struct ScriptedInput<C = Command> {
+    frames: VecDeque<Vec<C>>,
+    finished: bool,
+}
+
+impl<C> ScriptedInput<C> {
+    pub fn new(frames: Vec<Vec<C>>) -> ScriptedInput<C> {
+        ScriptedInput { frames: frames.into_iter().collect(), finished: false }
+    }
+}
+
+impl<C> Default for ScriptedInput<C> {
+    fn default() -> ScriptedInput<C> {
+        ScriptedInput::new(Vec::new())
+    }
+}
+
+impl<C> InputSource<C> for ScriptedInput<C> {
+    fn commands(&mut self) -> Vec<C> {
+        match self.frames.pop_front() {
+            Some(commands) => commands,
+            None => {
+                self.finished = true;
+                Vec::new()
+            }
+        }
+    }
+
+    fn is_quit(&self) -> bool {
+        self.finished
+    }
+}
+
+//Чем закончилась игра
+pub fn outcome_message(outcome: &Outcome) -> String {
+    match *outcome {
+        Outcome::Died(DeathCause::Frame) => "GAME OVER: HIT THE FRAME".to_string(),
+        Outcome::Died(DeathCause::Wall) => "GAME OVER: HIT A WALL".to_string(),
+        Outcome::Died(DeathCause::Tail) => "GAME OVER: BIT ITSELF".to_string(),
+        Outcome::Died(DeathCause::Snake) => "GAME OVER: HIT ANOTHER SNAKE".to_string(),
+        Outcome::BoardCleared => "YOU WIN: BOARD CLEARED".to_string(),
+        Outcome::Won(player) => format!("PLAYER {} WINS", player + 1),
+        Outcome::Draw => "DRAW".to_string(),
+    }
+}
+
+//Текст который нужно показать игроку на текущем этапе игры. Одинаковый во всех представлениях
+pub fn phase_message(phase: &Phase) -> String {
+    match *phase {
+        Phase::Title => "SNAKE - PRESS ENTER TO START".to_string(),
+        Phase::Countdown { remaining } => format!("{}", remaining.ceil()),
+        Phase::Playing => String::new(),
+        Phase::Paused => "PAUSED (P - CONTINUE, N - STEP)".to_string(),
+        Phase::GameOver(ref summary) => format!("{} IN {} TICKS - PRESS ENTER TO PLAY AGAIN",
+                                                outcome_message(&summary.outcome), summary.ticks),
+    }
+}
+
+//Змейкой управляет компьютер, а не игрок и не его автопилот.
+//Списки могут быть короче счета, например в снимке собранном вручную. Тогда это игрок
+fn is_bot(snapshot: &GameSnapshot, player: usize) -> bool {
+    snapshot.bots.get(player).cloned().unwrap_or(false) && !snapshot.autopilots.get(player).cloned().unwrap_or(false)
+}
+
+//Имя игрока в счете: P1, P2 для игроков и AI2, AI3 для компьютера
+pub fn player_name(snapshot: &GameSnapshot, player: usize) -> String {
+    format!("{}{}", if is_bot(snapshot, player) { "AI" } else { "P" }, player + 1)
+}
+
+//Строка со счетом над полем
+pub fn score_line(snapshot: &GameSnapshot) -> String {
+    let scores = &snapshot.scores;
+    let score = if scores.len() == 1 {
+        format!("SCORE: {}", scores[0])
+    } else {
+        scores.iter().enumerate()
+            .map(|(i, s)| format!("{}: {}", player_name(snapshot, i), s))
+            .collect::<Vec<String>>()
+            .join("  ")
+    };
+    format!("{}  MAX: {}", score, snapshot.max_score)
+}
+
+//Цикл представления: берем команды, обновляем игру и рисуем кадр пока окно открыто и игрок не вышел.
+//Возвращает игру в том виде в котором ее оставил игрок
+pub fn run<C, S: GameSource<C>, R: Renderer, I: InputSource<C>>(source: S, renderer: &mut R, input: &mut I) -> Result<S, Box<dyn Error>> {
+    let mut source = source;
+    let mut last = Instant::now();
+    while renderer.is_open() {
+        let commands = input.commands();
+        if input.is_quit() {
+            break;
+        }
+        let now = Instant::now();
+        let time_delta = now.duration_since(last).as_secs_f32();
+        last = now;
+        source = source.update(time_delta, commands);
+        renderer.render(&source.snapshot(), &source.status())?;
+    }
+    Ok(source)
+}
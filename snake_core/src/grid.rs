@@ -0,0 +1,118 @@
+//Entities. Сетка занятости клеток ---------------------------------------------
+
+use entities::{Frame, Point};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+//Что лежит в одной клетке поля.
+//Змейка после роста может несколько раз занимать одну клетку поэтому храним количество ее точек
+pub struct Cell {
+    pub snake: u16,
+    pub wall: bool,
+    pub food: bool,
+}
+
+impl Cell {
+    //В клетке ничего нет
+    pub fn is_free(&self) -> bool {
+        self.snake == 0 && !self.wall && !self.food
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+//Сетка клеток фрейма. Позволяет за постоянное время узнать что лежит в любой клетке
+//вместо перебора всех точек змейки, стен и еды
+pub struct Grid {
+    frame: Frame,
+    width: usize,
+    cells: Vec<Cell>,
+    //Сколько клеток занято хотя бы одной точкой змейки
+    snake_cells: usize,
+    //Сколько клеток доступно змейкам. Меняется только вместе с уровнем или формой поля
+    playable_cells: usize,
+}
+
+impl Grid {
+    //Пустая сетка размером с фрейм включая его границу
+    pub fn new(frame: &Frame) -> Grid {
+        let width = (frame.max_x - frame.min_x) as usize + 1;
+        let height = (frame.max_y - frame.min_y) as usize + 1;
+        Grid { frame: frame.clone(), width, cells: vec![Cell::default(); width * height], snake_cells: 0, playable_cells: 0 }
+    }
+
+    fn index(&self, point: &Point) -> Option<usize> {
+        if point.x < self.frame.min_x || point.x > self.frame.max_x
+            || point.y < self.frame.min_y || point.y > self.frame.max_y {
+            return None;
+        }
+        let x = (point.x - self.frame.min_x) as usize;
+        let y = (point.y - self.frame.min_y) as usize;
+        Some(y * self.width + x)
+    }
+
+    //Что лежит в клетке. За пределами фрейма клетки всегда пустые
+    pub fn cell(&self, point: &Point) -> Cell {
+        match self.index(point) {
+            Some(i) => self.cells[i],
+            None => Cell::default(),
+        }
+    }
+
+    //Свободна ли клетка от змейки, стен и еды
+    pub fn is_free(&self, point: &Point) -> bool {
+        self.cell(point).is_free()
+    }
+
+    fn change<F: FnOnce(&mut Cell)>(self, point: &Point, f: F) -> Grid {
+        let mut grid = self;
+        if let Some(i) = grid.index(point) {
+            f(&mut grid.cells[i]);
+        }
+        grid
+    }
+
+    //Запоминает сколько клеток доступно змейкам, чтобы не считать их на каждом шаге
+    pub fn with_playable_cells(self, playable_cells: usize) -> Grid {
+        Grid { playable_cells, ..self }
+    }
+
+    pub fn playable_cells(&self) -> usize {
+        self.playable_cells
+    }
+
+    //Сколько клеток занято змейками. Точки выросшей змейки в одной клетке считаются один раз
+    pub fn snake_cells(&self) -> usize {
+        self.snake_cells
+    }
+
+    //Точка змейки попала в клетку
+    pub fn add_snake(self, point: &Point) -> Grid {
+        let mut grid = self;
+        if let Some(i) = grid.index(point) {
+            if grid.cells[i].snake == 0 {
+                grid.snake_cells += 1;
+            }
+            grid.cells[i].snake = grid.cells[i].snake.saturating_add(1);
+        }
+        grid
+    }
+
+    //Точка змейки ушла из клетки
+    pub fn remove_snake(self, point: &Point) -> Grid {
+        let mut grid = self;
+        if let Some(i) = grid.index(point) {
+            if grid.cells[i].snake == 1 {
+                grid.snake_cells -= 1;
+            }
+            grid.cells[i].snake = grid.cells[i].snake.saturating_sub(1);
+        }
+        grid
+    }
+
+    pub fn set_wall(self, point: &Point, wall: bool) -> Grid {
+        self.change(point, |c| c.wall = wall)
+    }
+
+    pub fn set_food(self, point: &Point, food: bool) -> Grid {
+        self.change(point, |c| c.food = food)
+    }
+}
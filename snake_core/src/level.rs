@@ -0,0 +1,166 @@
+//Data Access Layer. Уровни ----------------------------------------------------------
+
+/*
+Уровень записывается обычным текстом. Каждая строка это ряд клеток поля сверху вниз:
+# - стена
+S - голова змейки в начале игры
+F - еда которая появится первой
+пробел - пустая клетка
+Направление змейки задается стрелкой (> < ^ v) в соседней с S клетке.
+Без стрелки змейка смотрит вправо. Крайние строки и столбцы это граница фрейма.
+Пример:
+#########
+#       #
+#  S>   #
+#    ## #
+#  F    #
+#########
+*/
+
+use std::error::Error;
+use std::fmt;
+use entities::{Coord, Direction, Frame, Point};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+//Ошибка разбора уровня с номером строки и столбца начиная с единицы
+pub struct LevelError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl LevelError {
+    fn new(line: usize, column: usize, message: &str) -> LevelError {
+        LevelError { line, column, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for LevelError {}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Описание уровня из которого собирается игра
+pub struct Level {
+    pub width: Coord,
+    pub height: Coord,
+    pub walls: Vec<Point>,
+    pub spawn: Point,
+    pub direction: Direction,
+    pub food: Vec<Point>,
+}
+
+//Пустое поле 30 на 30 внутри фрейма. Сторона четная, поэтому автопилот может обойти все поле по циклу
+impl Default for Level {
+    fn default() -> Level {
+        Level {
+            width: 32,
+            height: 32,
+            walls: Vec::new(),
+            spawn: Point { x: 15, y: 16 },
+            direction: Direction::Right,
+            food: Vec::new(),
+        }
+    }
+}
+
+impl Level {
+    //Фрейм уровня. Крайние клетки карты это его граница
+    pub fn frame(&self) -> Frame {
+        Frame { min_x: 0, min_y: 0, max_x: self.width - 1, max_y: self.height - 1 }
+    }
+
+    //Разбираем текст уровня. Первая строка текста это верх поля поэтому у нее самый большой y
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let lines: Vec<Vec<char>> = text.lines()
+            .map(|l| l.trim_end_matches('\r').chars().collect())
+            .collect();
+        let height = lines.iter().rposition(|l| !l.is_empty()).map_or(0, |i| i + 1);
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        if height < 3 || width < 3 {
+            return Err(LevelError::new(1, 1, "level must be at least 3x3 including the frame"));
+        }
+        if height > Coord::MAX as usize || width > Coord::MAX as usize {
+            return Err(LevelError::new(1, 1, &format!("level must be at most {0}x{0}", Coord::MAX)));
+        }
+        let mut walls = Vec::new();
+        let mut food = Vec::new();
+        let mut spawn: Option<(usize, usize)> = None;
+        let mut arrow: Option<(usize, usize, Direction)> = None;
+        for (row, line) in lines.iter().take(height).enumerate() {
+            for (column, c) in line.iter().enumerate() {
+                let on_frame = row == 0 || column == 0 || row == height - 1 || column == width - 1;
+                let position = (row + 1, column + 1);
+                let point = Point { x: column as Coord, y: (height - 1 - row) as Coord };
+                match *c {
+                    ' ' => {}
+                    '#' => if !on_frame { walls.push(point) },
+                    'S' | 'F' | '>' | '<' | '^' | 'v' if on_frame => {
+                        return Err(LevelError::new(position.0, position.1, &format!("'{}' can not be placed on the frame", c)));
+                    }
+                    'S' => {
+                        if spawn.is_some() {
+                            return Err(LevelError::new(position.0, position.1, "level has more than one snake spawn 'S'"));
+                        }
+                        spawn = Some(position);
+                    }
+                    'F' => food.push(point),
+                    '>' | '<' | '^' | 'v' => {
+                        if arrow.is_some() {
+                            return Err(LevelError::new(position.0, position.1, "level has more than one direction arrow"));
+                        }
+                        let direction = match *c {
+                            '>' => Direction::Right,
+                            '<' => Direction::Left,
+                            //Верх карты это больший y
+                            '^' => Direction::Bottom,
+                            _ => Direction::Top,
+                        };
+                        arrow = Some((position.0, position.1, direction));
+                    }
+                    _ => {
+                        return Err(LevelError::new(position.0, position.1, &format!("unexpected character '{}'", c)));
+                    }
+                }
+            }
+        }
+        let (line, column) = match spawn {
+            Some(s) => s,
+            None => return Err(LevelError::new(1, 1, "level has no snake spawn 'S'")),
+        };
+        let direction = match arrow {
+            None => Direction::Right,
+            Some((arrow_line, arrow_column, direction)) => {
+                let (expected_line, expected_column) = match direction {
+                    Direction::Right => (line, column + 1),
+                    Direction::Left => (line, column - 1),
+                    Direction::Bottom => (line - 1, column),
+                    Direction::Top => (line + 1, column),
+                };
+                if (arrow_line, arrow_column) != (expected_line, expected_column) {
+                    return Err(LevelError::new(arrow_line, arrow_column, "direction arrow must be next to 'S' and point away from it"));
+                }
+                direction
+            }
+        };
+        //Тело змейки лежит позади головы и не должно задевать стены и фрейм
+        let spawn = Point { x: (column - 1) as Coord, y: (height - line) as Coord };
+        for i in 1..3 {
+            let (x, y) = match direction {
+                Direction::Right => (spawn.x as isize - i, spawn.y as isize),
+                Direction::Left => (spawn.x as isize + i, spawn.y as isize),
+                Direction::Top => (spawn.x as isize, spawn.y as isize + i),
+                Direction::Bottom => (spawn.x as isize, spawn.y as isize - i),
+            };
+            let inside = x > 0 && y > 0 && x < width as isize - 1 && y < height as isize - 1;
+            if !inside || walls.contains(&Point { x: x as Coord, y: y as Coord }) {
+                return Err(LevelError::new(line, column, "no room for the snake body behind 'S'"));
+            }
+        }
+        Ok(Level { width: width as Coord, height: height as Coord, walls, spawn, direction, food })
+    }
+}
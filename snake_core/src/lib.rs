@@ -0,0 +1,46 @@
+/*Библиотека с логикой игры без привязки к графике.
+Ее можно использовать из ботов, тестов и утилит без открытия окна.
+Представление на three лежит в отдельном бинарнике game.
+*/
+extern crate rand;
+extern crate rand_pcg;
+extern crate bincode;
+extern crate png;
+extern crate gif;
+extern crate crc32fast;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+pub mod entities;
+pub mod grid;
+pub mod level;
+pub mod data_access;
+pub mod logic;
+pub mod ai;
+pub mod controller;
+pub mod replay;
+pub mod simulation;
+pub mod environment;
+pub mod frontend;
+pub mod picture;
+pub mod network;
+pub mod options;
+
+pub use entities::*;
+pub use grid::*;
+pub use level::*;
+pub use data_access::*;
+pub use logic::*;
+pub use ai::*;
+pub use controller::*;
+pub use replay::*;
+pub use simulation::*;
+pub use environment::*;
+pub use frontend::*;
+pub use picture::*;
+pub use network::*;
+pub use options::*;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,615 @@
+//Business Logic Layer------------------------------------------------------------
+
+use std::collections::{HashSet, VecDeque};
+use entities::{Coord, Direction, Food, FoodConfig, FoodKind, Frame, Point, Snake, SpeedCurve, Topology};
+use ai::{self, AiLevel};
+use data_access::{FoodGenerator, GameRng};
+use grid::Grid;
+use level::Level;
+
+//Сколько поворотов игрок может нажать наперед
+pub const MAX_QUEUED_TURNS: usize = 3;
+
+//Сколько секунд длится обратный отсчет перед началом игры
+pub const COUNTDOWN_SECONDS: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+//Причина гибели змейки
+pub enum DeathCause {
+    Frame,
+    Wall,
+    Tail,
+    //Голова врезалась в другую змейку или в ее голову
+    Snake,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+//Чем закончилась игра
+pub enum Outcome {
+    //Змейка погибла
+    Died(DeathCause),
+    //Змейка заняла все поле и еде больше негде появиться. Это победа
+    BoardCleared,
+    //В игре нескольких игроков в живых остался только игрок с этим номером
+    Won(usize),
+    //В игре нескольких игроков все змейки погибли одновременно
+    Draw,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Итоги одного игрока
+pub struct PlayerSummary {
+    pub score: usize,
+    pub length: usize,
+    //Почему погибла змейка. У выжившей змейки ничего
+    pub death: Option<DeathCause>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Итоги игры которые показываются после ее окончания
+pub struct GameSummary {
+    pub ticks: u64,
+    pub outcome: Outcome,
+    //Итоги каждого игрока по порядку номеров
+    pub players: Vec<PlayerSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+//Этапы игры. На каждом этапе игра по своему обрабатывает ввод и время
+pub enum Phase {
+    //Заставка перед первой игрой
+    Title,
+    //Обратный отсчет перед началом движения
+    Countdown { remaining: f32 },
+    //Змейка двигается
+    #[default]
+    Playing,
+    //Игра остановлена игроком
+    Paused,
+    //Змейка погибла или заняла все поле
+    GameOver(GameSummary),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Команды игрока
+pub enum Command {
+    //Повернуть змейку игрока с заданным номером. Первый игрок имеет номер 0
+    Turn(usize, Direction),
+    //Начать игру на заставке или после гибели змейки
+    Confirm,
+    //Поставить игру на паузу или снять с нее
+    Pause,
+    //На паузе сделать ровно один шаг змейкой
+    Step,
+    //Включить или выключить автопилот для змейки игрока с заданным номером
+    Autopilot(usize),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+//Игрок со своей змейкой, счетом и очередью поворотов
+pub struct Player {
+    pub snake: Snake,
+    pub score: usize,
+    //Повороты которые еще не применены. На каждом шаге применяется один
+    pub turns: VecDeque<Direction>,
+    //Почему погибла змейка. Погибшая змейка остается на поле препятствием до конца игры
+    pub death: Option<DeathCause>,
+    //Уровень компьютера который управляет змейкой. У человека ничего
+    pub bot: Option<AiLevel>,
+}
+
+impl Player {
+    pub fn new(snake: Snake) -> Player {
+        Player { snake, score: 0, turns: VecDeque::new(), death: None, bot: None }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    //Возвращает змейку в начальное положение и сбрасывает счет
+    fn reset(self) -> Player {
+        Player { bot: self.bot, ..Player::new(self.snake.reset()) }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+//Обьектное представление логики нашей игры
+pub struct Game {
+    //Игроки по порядку номеров. Всегда есть хотя бы один
+    pub players: Vec<Player>,
+    pub frame: Frame,
+    pub topology: Topology,
+    pub walls: Vec<Point>,
+    pub foods: Vec<Food>,
+    pub food_generator: FoodGenerator,
+    pub max_score: usize,
+    pub total_time: f32,
+    pub speed: SpeedCurve,
+    pub phase: Phase,
+    //Сколько шагов сделала змейка в текущей игре
+    pub ticks: u64,
+    //Зерно с которого началась текущая игра. По нему и по нажатиям игру можно повторить
+    pub seed: u64,
+    //Какие клетки заняты змейками, стенами и едой. Обновляется на каждом шаге,
+    //поэтому все проверки столкновений занимают постоянное время.
+    //Сетку можно собрать заново по остальным полям, поэтому она не сохраняется
+    #[serde(skip)]
+    pub grid: Grid,
+}
+
+impl Game {
+    //Конструктор для создания игры с фреймом заданной высоты и ширины.
+    //Одинаковое зерно и одинаковый ввод дают одинаковую игру.
+    pub fn new(height: Coord, width: Coord, seed: u64) -> Game {
+        let frame = Frame { min_x: 0, min_y: 0, max_x: width, max_y: height };
+        Game::with_generator(FoodGenerator::new(frame, seed), Snake::new(width / 2, height / 2), seed)
+    }
+
+    //Конструктор для создания игры с генератором случайных чисел переданным снаружи.
+    //Зерно такой игры неизвестно, поэтому повторить можно только следующие игры после restart
+    pub fn from_rng(height: Coord, width: Coord, rng: GameRng) -> Game {
+        let frame = Frame { min_x: 0, min_y: 0, max_x: width, max_y: height };
+        Game::with_generator(FoodGenerator::from_rng(frame, rng), Snake::new(width / 2, height / 2), 0)
+    }
+
+    //Собирает игру по уровню: фрейм, стены, начальное положение змейки и заданная уровнем еда
+    pub fn from_level(level: &Level, seed: u64) -> Game {
+        let generator = FoodGenerator::new(level.frame(), seed)
+            .with_walls(level.walls.clone())
+            .with_fixed_food(level.food.clone());
+        Game::with_generator(generator, Snake::spawn(level.spawn.clone(), level.direction.clone()), seed)
+            .with_walls(level.walls.clone())
+    }
+
+    fn with_generator(generator: FoodGenerator, snake: Snake, seed: u64) -> Game {
+        let frame = generator.frame.clone();
+        Game {
+            players: vec![Player::new(snake)],
+            grid: Grid::new(&frame),
+            frame,
+            topology: Topology::default(),
+            walls: Vec::new(),
+            foods: Vec::new(),
+            food_generator: generator,
+            max_score: 0,
+            total_time: 0f32,
+            speed: SpeedCurve::default(),
+            phase: Phase::default(),
+            ticks: 0,
+            seed,
+        }
+            .with_grid()
+            .fill_food()
+    }
+
+    //Заново заполняет сетку по змейкам, стенам и еде.
+    //Нужно вызвать если поля игры были изменены напрямую
+    pub fn with_grid(self) -> Game {
+        let mut grid = Grid::new(&self.frame).with_playable_cells(self.food_generator.playable_cells());
+        for p in self.players.iter().flat_map(|p| p.snake.points.iter()) {
+            grid = grid.add_snake(p);
+        }
+        for w in self.walls.iter() {
+            grid = grid.set_wall(w, true);
+        }
+        for f in self.foods.iter() {
+            grid = grid.set_food(&f.point, true);
+        }
+        Game { grid, ..self }
+    }
+
+    //Заменяет змейку первого игрока
+    pub fn with_snake(self, snake: Snake) -> Game {
+        let mut game = self;
+        game.players[0].snake = snake;
+        game.with_grid()
+    }
+
+    //Добавляет игрока с заданной змейкой. Еда под новой змейкой убирается
+    pub fn with_player(self, snake: Snake) -> Game {
+        let mut game = self;
+        game.foods.retain(|f| !snake.points.contains(&f.point));
+        game.players.push(Player::new(snake));
+        game.with_grid().fill_food()
+    }
+
+    //Отдает змейку игрока под управление компьютера или возвращает ее человеку
+    pub fn with_bot(self, player: usize, bot: Option<AiLevel>) -> Game {
+        let mut game = self;
+        if let Some(p) = game.players.get_mut(player) {
+            p.bot = bot;
+            p.turns.clear();
+        }
+        game
+    }
+
+    //Включает автопилот для змейки человека или выключает его. Змейки компьютера не меняются
+    fn toggle_autopilot(self, player: usize) -> Game {
+        match self.players.get(player).map(|p| p.bot) {
+            Some(None) => self.with_bot(player, Some(AiLevel::Autopilot)),
+            Some(Some(AiLevel::Autopilot)) => self.with_bot(player, None),
+            _ => self,
+        }
+    }
+
+    //Змейка для второго игрока. Она начинает напротив первого игрока относительно центра поля
+    pub fn mirrored_snake(&self) -> Snake {
+        self.players[0].snake.mirrored(&self.frame)
+    }
+
+    //Ищем место для новой змейки: три свободные клетки в ряд и две свободные клетки перед головой.
+    //Сначала пробуем место напротив первого игрока, иначе выбираем самое далекое от голов остальных змеек
+    pub fn free_snake(&self) -> Option<Snake> {
+        let mut candidates = Vec::new();
+        for y in self.frame.min_y..=self.frame.max_y {
+            for x in self.frame.min_x..=self.frame.max_x {
+                candidates.push(Snake::spawn(Point { x, y }, Direction::Right));
+                candidates.push(Snake::spawn(Point { x, y }, Direction::Left));
+            }
+        }
+        let is_open = |p: &Point| {
+            let cell = self.grid.cell(p);
+            cell.snake == 0 && !cell.wall && !self.frame.intersects(p)
+        };
+        let heads: Vec<Point> = self.players.iter().map(|p| p.snake.head()).collect();
+        let distance = |p: &Point| heads.iter()
+            .map(|h| (h.x as isize - p.x as isize).abs() + (h.y as isize - p.y as isize).abs())
+            .min()
+            .unwrap_or(0);
+        let fits = |snake: &Snake| {
+            let ahead = snake.head().step(&snake.direction, &self.frame, Topology::Walled);
+            let further = ahead.step(&snake.direction, &self.frame, Topology::Walled);
+            let body: HashSet<&Point> = snake.points.iter().collect();
+            body.len() == snake.points.len()
+                && snake.points.iter().all(&is_open)
+                && is_open(&ahead) && is_open(&further)
+        };
+        let mirrored = self.mirrored_snake();
+        if fits(&mirrored) {
+            return Some(mirrored);
+        }
+        let mut best: Option<(isize, Snake)> = None;
+        for snake in candidates.into_iter().filter(|s| fits(s)) {
+            let d = distance(&snake.head());
+            match best {
+                Some((best_distance, _)) if best_distance >= d => {}
+                _ => best = Some((d, snake)),
+            }
+        }
+        best.map(|(_, snake)| snake)
+    }
+
+    //Заменяет еду на поле
+    pub fn with_foods(self, foods: Vec<Food>) -> Game {
+        Game { foods, ..self }.with_grid()
+    }
+
+    //Меняет форму поля игры
+    pub fn with_topology(self, topology: Topology) -> Game {
+        let food_generator = self.food_generator.with_topology(topology);
+        Game { topology, food_generator, ..self }.with_grid()
+    }
+
+    //Добавляет стены внутри фрейма. Если еда оказалась на стене то создаем новую
+    pub fn with_walls(self, walls: Vec<Point>) -> Game {
+        let food_generator = self.food_generator.with_walls(walls.clone());
+        let foods = self.foods.into_iter().filter(|f| !walls.contains(&f.point)).collect();
+        Game { walls, food_generator, foods, ..self }.with_grid().fill_food()
+    }
+
+    //Задает сколько еды лежит на поле и какая она
+    pub fn with_food_config(self, config: FoodConfig) -> Game {
+        let food_generator = self.food_generator.with_config(config);
+        Game { food_generator, ..self }.fill_food()
+    }
+
+    //Задает как меняется скорость змейки
+    pub fn with_speed(self, speed: SpeedCurve) -> Game {
+        Game { speed, ..self }
+    }
+
+    //Рекорд прошлых игр. Игра сама его не читает и не сохраняет, это делает представление
+    pub fn with_max_score(self, max_score: usize) -> Game {
+        Game { max_score, ..self }
+    }
+
+    //Сколько секунд сейчас проходит между шагами змейки.
+    //Когда игроков несколько скорость задает самая длинная змейка и лучший счет
+    pub fn tick_interval(&self) -> f32 {
+        let length = self.players.iter().map(|p| p.snake.points.len()).max().unwrap_or(0);
+        let score = self.players.iter().map(|p| p.score).max().unwrap_or(0);
+        self.speed.interval(length, score)
+    }
+
+    //Выкладываем новую еду пока ее на поле меньше чем задано настройками
+    //и пока на поле есть свободные клетки
+    fn fill_food(self) -> Game {
+        let mut game = self;
+        while game.foods.len() < game.food_generator.config.count {
+            let (generator, food) = game.food_generator.generate(&game.grid);
+            game.food_generator = generator;
+            match food {
+                Some(food) => {
+                    game.grid = game.grid.set_food(&food.point, true);
+                    game.foods.push(food);
+                }
+                None => break,
+            }
+        }
+        game
+    }
+
+    //Удлиняет змейку игрока на count точек. Новые точки появляются на месте хвоста
+    fn grow_snake(self, player: usize, count: usize) -> Game {
+        let mut game = self;
+        for _ in 0..count {
+            if let Some(tail) = game.players[player].snake.points.back().cloned() {
+                game.players[player].snake = std::mem::take(&mut game.players[player].snake).grow();
+                game.grid = game.grid.add_snake(&tail);
+            }
+        }
+        game
+    }
+
+    //Укорачивает змейку игрока на count точек с хвоста
+    fn shrink_snake(self, player: usize, count: usize) -> Game {
+        let mut game = self;
+        for _ in 0..count {
+            if game.players[player].snake.points.len() > 1 {
+                if let Some(tail) = game.players[player].snake.points.back().cloned() {
+                    game.players[player].snake = std::mem::take(&mut game.players[player].snake).shrink(1);
+                    game.grid = game.grid.remove_snake(&tail);
+                }
+            }
+        }
+        game
+    }
+
+    //Начинает игру с заданного этапа. Новая игра сразу начинается с движения змейки
+    pub fn with_phase(self, phase: Phase) -> Game {
+        Game { phase, ..self }
+    }
+
+    //Обновляем игру в зависимости от этапа.
+    //Время идет только во время обратного отсчета и игры
+    pub fn update(self, time_delta_in_seconds: f32) -> Game {
+        match self.phase.clone() {
+            Phase::Countdown { remaining } if remaining > time_delta_in_seconds => {
+                Game { phase: Phase::Countdown { remaining: remaining - time_delta_in_seconds }, ..self }
+            }
+            Phase::Countdown { .. } => Game { phase: Phase::Playing, total_time: 0f32, ..self },
+            Phase::Playing => self.play(time_delta_in_seconds),
+            Phase::Title | Phase::Paused | Phase::GameOver(_) => self,
+        }
+    }
+
+    // Проверяем, прошло ли достаточно времени с момента когда мы в последний раз
+    //двигали нашу змейку и если да то передвигаем ее
+    // иначе ничего не делаем
+    fn play(self, time_delta_in_seconds: f32) -> Game {
+        let (game, is_moving) = self.is_time_to_move(time_delta_in_seconds);
+        if is_moving {
+            game.step()
+        } else {
+            game
+        }
+    }
+
+    //Передвигаем все живые змейки на одну точку и проверяем столкновения голов с остальными обьектами игры.
+    //Сначала двигаются все змейки и только потом проверяются столкновения,
+    //поэтому номер игрока не дает преимущества при лобовом столкновении
+    fn step(self) -> Game {
+        let mut game = self.think();
+        for player in game.players.iter_mut().filter(|p| p.is_alive()) {
+            let mut snake = std::mem::take(&mut player.snake);
+            if let Some(direction) = player.turns.pop_front() {
+                snake = snake.turn(direction);
+            }
+            //Хвост уходит из своей клетки, а голова занимает новую
+            let tail = snake.points.back().cloned();
+            snake = snake.move_snake(&game.frame, game.topology);
+            if let Some(tail) = tail {
+                game.grid = game.grid.remove_snake(&tail);
+            }
+            game.grid = game.grid.add_snake(&snake.head());
+            player.snake = snake;
+        }
+        game.ticks += 1;
+        let deaths: Vec<(usize, DeathCause)> = (0..game.players.len())
+            .filter(|i| game.players[*i].is_alive())
+            .filter_map(|i| game.collision(i).map(|cause| (i, cause)))
+            .collect();
+        for (i, cause) in deaths {
+            game.players[i].death = Some(cause);
+        }
+        for i in 0..game.players.len() {
+            if game.players[i].is_alive() {
+                game = game.try_eat(i);
+            }
+        }
+        game.check_game_over()
+    }
+
+    //Компьютерные игроки выбирают направление перед каждым шагом.
+    //Их повороты проходят через handle_input как нажатия человека, поэтому правила для всех одинаковы
+    fn think(self) -> Game {
+        let mut game = self;
+        for i in 0..game.players.len() {
+            let player = &game.players[i];
+            if let (Some(level), true, true) = (player.bot, player.is_alive(), player.turns.is_empty()) {
+                if let Some(direction) = ai::choose_direction(&game, i, level) {
+                    game = game.handle_input(i, direction);
+                }
+            }
+        }
+        game
+    }
+
+    //С чем столкнулась голова змейки игрока. На поле без стен граница фрейма не опасна
+    pub fn collision(&self, player: usize) -> Option<DeathCause> {
+        let snake = &self.players[player].snake;
+        let head = snake.head();
+        let cell = self.grid.cell(&head);
+        if cell.snake > 1 {
+            if snake.intersects_tail() {
+                Some(DeathCause::Tail)
+            } else {
+                Some(DeathCause::Snake)
+            }
+        } else if cell.wall {
+            Some(DeathCause::Wall)
+        } else if self.topology == Topology::Walled && self.frame.intersects(&head) {
+            Some(DeathCause::Frame)
+        } else {
+            None
+        }
+    }
+
+    //Решаем закончилась ли игра. Один игрок проигрывает когда гибнет его змейка.
+    //Из нескольких игроков побеждает последний выживший.
+    //Если погибли все люди а компьютерные змейки еще живы то игра тоже заканчивается.
+    //Если змейки заняли все поле или еду больше некуда положить то это победа всех выживших.
+    //Поле считаем по клеткам сетки, а не по длине: выросшая змейка несколько раз занимает одну клетку
+    fn check_game_over(self) -> Game {
+        let alive: Vec<usize> = (0..self.players.len()).filter(|i| self.players[*i].is_alive()).collect();
+        let humans = self.players.iter().filter(|p| p.bot.is_none()).count();
+        let humans_alive = alive.iter().filter(|i| self.players[**i].bot.is_none()).count();
+        let outcome = if self.players.len() == 1 {
+            self.players[0].death.map(Outcome::Died)
+        } else if humans == 1 && humans_alive == 0 {
+            //Единственный человек проиграл компьютеру. Показываем почему погибла его змейка, а не победу компьютера
+            self.players.iter().find(|p| p.bot.is_none()).and_then(|p| p.death).map(Outcome::Died)
+        } else {
+            match alive.len() {
+                0 => Some(Outcome::Draw),
+                1 => Some(Outcome::Won(alive[0])),
+                _ if humans > 0 && humans_alive == 0 => self.players[0].death.map(Outcome::Died).or(Some(Outcome::Draw)),
+                _ => None,
+            }
+        };
+        match outcome {
+            Some(outcome) => self.game_over(outcome),
+            None if self.grid.snake_cells() >= self.grid.playable_cells()
+                || (self.foods.is_empty() && self.food_generator.config.count > 0) => {
+                self.game_over(Outcome::BoardCleared)
+            }
+            None => self,
+        }
+    }
+
+    //Игра закончилась. Запоминаем итоги игры
+    fn game_over(self, outcome: Outcome) -> Game {
+        let players = self.players.iter()
+            .map(|p| PlayerSummary { score: p.score, length: p.snake.points.len(), death: p.death })
+            .collect();
+        let summary = GameSummary { ticks: self.ticks, outcome, players };
+        Game { phase: Phase::GameOver(summary), ..self }
+    }
+
+    //Начинаем новую игру с зерном от генератора еды
+    fn restart(self) -> Game {
+        let (food_generator, seed) = self.food_generator.next_seed();
+        Game { food_generator, ..self }.restart_with_seed(seed)
+    }
+
+    //Возвращаем змейки в начальное положение, сбрасываем счет и еду и начинаем обратный отсчет.
+    //Еда новой игры зависит только от зерна, поэтому игру можно повторить по зерну и нажатиям
+    pub fn restart_with_seed(self, seed: u64) -> Game {
+        let players = self.players.into_iter().map(Player::reset).collect();
+        let food_generator = self.food_generator.reseed(seed);
+        Game {
+            players,
+            foods: Vec::new(),
+            food_generator,
+            seed,
+            total_time: 0f32,
+            ticks: 0,
+            phase: Phase::Countdown { remaining: COUNTDOWN_SECONDS },
+            ..self
+        }
+            .with_grid()
+            .fill_food()
+    }
+
+    //Ровно один шаг змеек независимо от прошедшего времени.
+    //Нужен для повтора записанной игры где важны шаги а не время кадров
+    pub fn advance(self) -> Game {
+        match self.phase {
+            Phase::Playing => Game { total_time: 0f32, ..self }.step(),
+            _ => self,
+        }
+    }
+
+    //Проверяем, настало ли время для того чтобы передвинуть змейку.
+    #[allow(clippy::wrong_self_convention)]
+    fn is_time_to_move(self, time_delta_in_seconds: f32) -> (Game, bool) {
+        let time_to_move = self.tick_interval();
+        let mut game = self;
+        game.total_time += time_delta_in_seconds;
+        if game.total_time > time_to_move {
+            game.total_time -= time_to_move;
+            (game, true)
+        } else {
+            (game, false)
+        }
+    }
+
+    //Проверяем, сьела ли змейка игрока еду и если да
+    // то создаем новую еду и применяем эффект еды
+    fn try_eat(self, player: usize) -> Game {
+        let mut game = self;
+        let head = game.players[player].snake.head();
+        if !game.grid.cell(&head).food {
+            return game;
+        }
+        if let Some(index) = game.foods.iter().position(|f| f.point.intersects(&head)) {
+            let food = game.foods.remove(index);
+            game.grid = game.grid.set_food(&food.point, false);
+            let config = game.food_generator.config.clone();
+            let (eaten, points) = match food.kind {
+                FoodKind::Normal => (game.grow_snake(player, 1), 1),
+                FoodKind::Bonus => (game.grow_snake(player, 1), config.bonus_points),
+                FoodKind::Poison => (game.shrink_snake(player, config.poison_shrink), 0),
+                FoodKind::Golden => (game.grow_snake(player, config.golden_growth), 1),
+            };
+            game = eaten.fill_food();
+            game.players[player].score += points;
+            game.max_score = game.max_score.max(game.players[player].score);
+        };
+        game
+    }
+
+    // Запоминаем поворот змейки игрока. Он будет применен на одном из следующих шагов.
+    //Поворот назад в шею змейки и поворот в ту же сторону отбрасываются.
+    //Повороты несуществующих и погибших игроков тоже отбрасываются
+    pub fn handle_input(self, player: usize, input: Direction) -> Game {
+        let mut game = self;
+        if let Some(p) = game.players.get_mut(player).filter(|p| p.is_alive()) {
+            let last = p.turns.back().unwrap_or(&p.snake.direction).clone();
+            if input != last && input != last.opposite() && p.turns.len() < MAX_QUEUED_TURNS {
+                p.turns.push_back(input);
+            }
+        }
+        game
+    }
+
+    //Обрабатываем команду игрока. Каждый этап принимает только свои команды
+    pub fn handle_command(self, command: Command) -> Game {
+        match (self.phase.clone(), command) {
+            (Phase::Title, Command::Confirm) | (Phase::GameOver(_), Command::Confirm) => self.restart(),
+            //Змейками компьютера человек не управляет
+            (Phase::Playing, Command::Turn(player, _)) | (Phase::Paused, Command::Turn(player, _))
+                if self.players.get(player).is_some_and(|p| p.bot.is_some()) => self,
+            (Phase::Playing, Command::Turn(player, direction)) | (Phase::Paused, Command::Turn(player, direction)) => {
+                self.handle_input(player, direction)
+            }
+            (Phase::Playing, Command::Pause) => Game { phase: Phase::Paused, ..self },
+            (Phase::Paused, Command::Pause) | (Phase::Paused, Command::Confirm) => Game { phase: Phase::Playing, ..self },
+            //Время при этом не идет, поэтому следующий шаг после снятия с паузы будет в свой срок
+            (Phase::Paused, Command::Step) => self.step(),
+            (_, Command::Autopilot(player)) => self.toggle_autopilot(player),
+            _ => self,
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//Data Access Layer. Игра по сети ----------------------------------------------------
+
+/*
+Сервер владеет единственной игрой и каждый свой шаг рассылает ее снимок всем клиентам.
+Клиенты присылают только команды. Сообщения идут по TCP, каждое сообщение это
+длина в 4 байта (little endian) и сообщение сериализованное с помощью bincode.
+*/
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use controller::{GameController, GameSnapshot};
+use logic::Command;
+
+//Порт сервера по умолчанию
+pub const DEFAULT_PORT: u16 = 7878;
+
+//Сколько секунд между шагами сервера
+pub const SERVER_TICK: f32 = 1.0 / 60.0;
+
+//Сообщения больше этого размера считаем ошибкой чтобы не выделять память по мусорной длине
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+//Сколько снимков может ждать отправки одному клиенту. Клиент который отстал сильнее отключается,
+//чтобы один зависший клиент не останавливал игру остальных
+const CLIENT_QUEUE: usize = 30;
+
+//Сколько ждем пока клиент примет сообщение
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+//Сообщения от сервера клиенту
+pub enum ServerMessage {
+    //Первое сообщение после подключения. Номер игрока клиента или ничего если все места заняты
+    //и клиент только смотрит
+    Welcome { player: Option<usize> },
+    //Состояние игры после очередного шага сервера
+    State(GameSnapshot),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+//Сообщения от клиента серверу
+pub enum ClientMessage {
+    //Команда игрока. Номер игрока в команде сервер заменяет на номер этого клиента
+    Command(Command),
+}
+
+//Сообщение вместе с его длиной, готовое к отправке
+fn encode_message<T: Serialize>(message: &T) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = bincode::serialize(message)?;
+    let mut frame = (bytes.len() as u32).to_le_bytes().to_vec();
+    frame.extend(bytes);
+    Ok(frame)
+}
+
+//Записывает одно сообщение в поток
+pub fn send_message<T: Serialize, W: Write>(stream: &mut W, message: &T) -> Result<(), Box<dyn Error>> {
+    stream.write_all(&encode_message(message)?)?;
+    stream.flush()?;
+    Ok(())
+}
+
+//Читает одно сообщение из потока. Блокируется пока сообщение не придет целиком
+pub fn receive_message<T: DeserializeOwned, R: Read>(stream: &mut R) -> Result<T, Box<dyn Error>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(format!("message of {} bytes is too large", length).into());
+    }
+    let mut bytes = vec![0u8; length];
+    stream.read_exact(&mut bytes)?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+//Подключенный к серверу клиент. Сообщения ему пишет отдельный поток из очереди,
+//поэтому медленный клиент не задерживает шаг сервера
+struct Client {
+    stream: TcpStream,
+    player: Option<usize>,
+    outbox: SyncSender<Arc<Vec<u8>>>,
+}
+
+impl Client {
+    //Ставим сообщение в очередь клиента. Ложь если очередь переполнена или поток записи уже закончился
+    fn send(&self, message: &Arc<Vec<u8>>) -> bool {
+        match self.outbox.try_send(message.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+impl Drop for Client {
+    //Закрываем соединение чтобы потоки чтения и записи клиента закончились
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+//Сервер игры. Только он двигает змейки, поэтому у всех клиентов одна и та же игра
+pub struct GameServer {
+    pub controller: GameController,
+    listener: TcpListener,
+    clients: Vec<Client>,
+    //Команды от потоков чтения клиентов вместе с номером игрока. У зрителя номера нет
+    commands: Receiver<(Option<usize>, Command)>,
+    sender: Sender<(Option<usize>, Command)>,
+    //Сколько снимков не удалось сериализовать. Такие шаги клиенты не видят
+    encode_errors: usize,
+}
+
+impl GameServer {
+    //Начинает слушать адрес. Порт 0 выбирает свободный порт
+    pub fn bind<A: ToSocketAddrs>(address: A, controller: GameController) -> Result<GameServer, Box<dyn Error>> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let (sender, commands) = channel();
+        Ok(GameServer { controller, listener, clients: Vec::new(), commands, sender, encode_errors: 0 })
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, Box<dyn Error>> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    //Первый номер игрока-человека который еще не занят клиентом
+    fn free_player(&self) -> Option<usize> {
+        (0..self.controller.game.players.len())
+            .filter(|i| !self.controller.is_bot(*i))
+            .find(|i| self.clients.iter().all(|c| c.player != Some(*i)))
+    }
+
+    //Принимаем новых клиентов. Для каждого запускаем поток который читает его команды
+    //и поток который пишет ему сообщения из очереди. Приветствие тоже идет через очередь,
+    //поэтому медленный клиент не задерживает шаг сервера даже при подключении
+    fn accept(self) -> GameServer {
+        let mut server = self;
+        loop {
+            let stream = match server.listener.accept() {
+                Ok((stream, _)) => stream,
+                //WouldBlock значит новых подключений пока нет
+                Err(_) => break,
+            };
+            let player = server.free_player();
+            let welcome = match encode_message(&ServerMessage::Welcome { player }) {
+                Ok(welcome) => welcome,
+                Err(_) => {
+                    server.encode_errors += 1;
+                    continue;
+                }
+            };
+            let streams = stream.set_nonblocking(false)
+                .and_then(|_| stream.set_nodelay(true))
+                .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+                .and_then(|_| Ok((stream.try_clone()?, stream.try_clone()?)));
+            if let Ok((mut reader, mut writer)) = streams {
+                let sender = server.sender.clone();
+                std::thread::spawn(move || {
+                    while let Ok(ClientMessage::Command(command)) = receive_message(&mut reader) {
+                        if sender.send((player, command)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                let (outbox, queue) = sync_channel::<Arc<Vec<u8>>>(CLIENT_QUEUE);
+                std::thread::spawn(move || {
+                    while let Ok(message) = queue.recv() {
+                        if writer.write_all(&message).and_then(|_| writer.flush()).is_err() {
+                            break;
+                        }
+                    }
+                });
+                let client = Client { stream, player, outbox };
+                //Очередь нового клиента пуста, поэтому приветствие в нее всегда помещается
+                if client.send(&Arc::new(welcome)) {
+                    server.clients.push(client);
+                }
+            }
+        }
+        server
+    }
+
+    //Один шаг сервера: принимаем клиентов, применяем их команды, двигаем игру и рассылаем снимок
+    pub fn tick(self, time_delta: f32) -> GameServer {
+        let mut server = self.accept();
+        let mut commands = Vec::new();
+        while let Ok((player, command)) = server.commands.try_recv() {
+            commands.extend(for_player(command, player));
+        }
+        server.controller = server.controller.update(time_delta, commands);
+        //Снимок сериализуем один раз для всех клиентов.
+        //Клиенты которые отключились или не успевают принимать снимки отключаются
+        match encode_message(&ServerMessage::State(server.controller.snapshot())) {
+            Ok(message) => {
+                let message = Arc::new(message);
+                server.clients.retain(|c| c.send(&message));
+            }
+            Err(_) => server.encode_errors += 1,
+        }
+        server
+    }
+
+    //Сколько клиентов подключено
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    //Сколько сообщений сервер не смог сериализовать с момента запуска
+    pub fn encode_errors(&self) -> usize {
+        self.encode_errors
+    }
+
+    //Бесконечный цикл сервера с шагом SERVER_TICK
+    pub fn run(self) {
+        let mut server = self;
+        let mut last = Instant::now();
+        loop {
+            let now = Instant::now();
+            let time_delta = now.duration_since(last).as_secs_f32();
+            last = now;
+            server = server.tick(time_delta);
+            let spent = now.elapsed();
+            let tick = Duration::from_secs_f32(SERVER_TICK);
+            if spent < tick {
+                std::thread::sleep(tick - spent);
+            }
+        }
+    }
+}
+
+//Клиент может управлять только своей змейкой. Зритель игрой не управляет совсем:
+//ни пауза, ни шаг, ни начало игры от него не принимаются
+fn for_player(command: Command, player: Option<usize>) -> Option<Command> {
+    let player = player?;
+    Some(match command {
+        Command::Turn(_, direction) => Command::Turn(player, direction),
+        Command::Autopilot(_) => Command::Autopilot(player),
+        command => command,
+    })
+}
+
+//Игра на сервере с точки зрения клиента. Хранит последний присланный снимок
+pub struct RemoteGame {
+    stream: TcpStream,
+    //Приветствие сервера. Пока его нет клиент не знает свой номер и команды не отправляет
+    welcome: Option<Option<usize>>,
+    messages: Receiver<ServerMessage>,
+    snapshot: GameSnapshot,
+    connected: bool,
+}
+
+impl RemoteGame {
+    //Подключаемся к серверу. Сообщения сервера читает отдельный поток,
+    //поэтому подключение не ждет пока сервер примет клиента
+    pub fn connect<A: ToSocketAddrs>(address: A) -> Result<RemoteGame, Box<dyn Error>> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let (sender, messages) = channel();
+        std::thread::spawn(move || {
+            while let Ok(message) = receive_message::<ServerMessage, _>(&mut reader) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(RemoteGame { stream, welcome: None, messages, snapshot: GameSnapshot::default(), connected: true })
+    }
+
+    //Номер игрока этого клиента. Ничего если клиент только смотрит или сервер еще не ответил
+    pub fn player(&self) -> Option<usize> {
+        self.welcome.unwrap_or(None)
+    }
+
+    //Сервер уже принял клиента
+    pub fn is_welcomed(&self) -> bool {
+        self.welcome.is_some()
+    }
+
+    //Связь с сервером еще есть
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    //Отправляем команды серверу и берем самый свежий снимок.
+    //Время здесь не нужно, игру двигает сервер
+    pub fn update(self, commands: Vec<Command>) -> RemoteGame {
+        let mut remote = self;
+        loop {
+            match remote.messages.try_recv() {
+                Ok(ServerMessage::Welcome { player }) => remote.welcome = Some(player),
+                Ok(ServerMessage::State(snapshot)) => remote.snapshot = snapshot,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    remote.connected = false;
+                    break;
+                }
+            }
+        }
+        if remote.player().is_some() {
+            for command in commands {
+                if send_message(&mut remote.stream, &ClientMessage::Command(command)).is_err() {
+                    remote.connected = false;
+                }
+            }
+        }
+        remote
+    }
+
+    pub fn snapshot(&self) -> &GameSnapshot {
+        &self.snapshot
+    }
+}
@@ -0,0 +1,41 @@
+//Presentation Layer
+//Разбор параметров командной строки, общий для всех бинарников.
+//Параметры задаются парами "--имя значение" или флагами "--имя" без значения.
+//Значение не может начинаться с "--", иначе его не отличить от следующего параметра
+use std::fmt::Display;
+use std::str::FromStr;
+
+//Параметр ли это, а не значение
+fn is_name(arg: &str) -> bool {
+    arg.starts_with("--")
+}
+
+//Значение параметра вида --name <значение>. Значения других параметров пропускаем.
+//Если за параметром сразу идет следующий, как в "--level --food 3", то у него нет значения и получаем None
+pub fn option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        if !is_name(arg) {
+            continue;
+        }
+        let value = args.next_if(|value| !is_name(value));
+        if arg == name {
+            return value;
+        }
+    }
+    None
+}
+
+//Разбираем значение параметра если он указан. Что делать с ошибкой решает программа
+pub fn parse_option<T: FromStr>(args: &[String], name: &str) -> Result<Option<T>, String>
+    where T::Err: Display {
+    option(args, name)
+        .map(|value| value.parse::<T>().map_err(|e| format!("{} {}: {}", name, value, e)))
+        .transpose()
+}
+
+//Значение параметра или значение по умолчанию
+pub fn parse<T: FromStr>(args: &[String], name: &str, default: T) -> Result<T, String>
+    where T::Err: Display {
+    parse_option(args, name).map(|value| value.unwrap_or(default))
+}
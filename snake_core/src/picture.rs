@@ -0,0 +1,404 @@
+//Presentation Layer. Картинка поля без видеокарты ----------------------------------
+
+/*
+Снимок игры рисуется в память на процессоре или в текст SVG.
+Так картинку можно приложить к ошибке, вставить в документацию
+или сравнить с эталоном в тестах на машине без графики.
+Сверху идет строка со счетом, под ней поле. Первая строка поля это самый большой y, как в файлах уровней.
+*/
+
+use std::error::Error;
+use controller::{GameSnapshot, PointDto, PointDtoType};
+use entities::Topology;
+use frontend::score_line;
+use logic::Phase;
+use replay::{Replay, ReplayPlayer};
+
+//Цвет точки: красный, зеленый, синий
+pub type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Цвета картинки
+pub struct Theme {
+    pub background: Rgb,
+    pub text: Rgb,
+    pub frame: Rgb,
+    //Фрейм поля без стен рисуется точками этого цвета
+    pub dots: Rgb,
+    pub wall: Rgb,
+    //Голова и хвост змейки первого игрока
+    pub head: Rgb,
+    pub tail: Rgb,
+    pub food: Rgb,
+    pub bonus_food: Rgb,
+    pub poison_food: Rgb,
+    pub golden_food: Rgb,
+    //Голова и хвост змеек остальных игроков и компьютера, по кругу
+    pub others: Vec<(Rgb, Rgb)>,
+}
+
+impl Theme {
+    //Темный фон и цвета как в 3D игре и в терминале
+    pub fn dark() -> Theme {
+        Theme {
+            background: [16, 16, 16],
+            text: [255, 255, 255],
+            frame: [0, 0, 255],
+            dots: [96, 96, 96],
+            wall: [160, 160, 160],
+            head: [255, 0, 0],
+            tail: [255, 255, 0],
+            food: [0, 255, 0],
+            bonus_food: [0, 255, 255],
+            poison_food: [128, 0, 128],
+            golden_food: [255, 215, 0],
+            others: vec![
+                ([255, 0, 255], [128, 0, 128]),
+                ([255, 128, 0], [255, 208, 160]),
+                ([0, 128, 128], [0, 255, 255]),
+                ([128, 0, 255], [208, 160, 255]),
+            ],
+        }
+    }
+
+    //Светлый фон для документации и печати
+    pub fn light() -> Theme {
+        Theme {
+            background: [250, 250, 245],
+            text: [32, 32, 32],
+            frame: [48, 64, 160],
+            dots: [176, 176, 176],
+            wall: [96, 96, 96],
+            head: [200, 0, 0],
+            tail: [230, 150, 0],
+            food: [0, 160, 0],
+            bonus_food: [0, 150, 200],
+            poison_food: [140, 0, 140],
+            golden_food: [200, 160, 0],
+            others: vec![
+                ([180, 0, 180], [230, 130, 230]),
+                ([220, 100, 0], [240, 180, 120]),
+                ([0, 110, 110], [80, 190, 190]),
+                ([90, 0, 200], [170, 130, 240]),
+            ],
+        }
+    }
+
+    //Зеленый экран старых телефонов
+    pub fn retro() -> Theme {
+        let dark = [40, 56, 24];
+        let middle = [88, 112, 48];
+        Theme {
+            background: [156, 188, 88],
+            text: dark,
+            frame: dark,
+            dots: middle,
+            wall: dark,
+            head: dark,
+            tail: middle,
+            food: dark,
+            bonus_food: middle,
+            poison_food: middle,
+            golden_food: dark,
+            others: vec![(middle, middle)],
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Theme, String> {
+        match s.to_lowercase().as_str() {
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            "retro" => Ok(Theme::retro()),
+            _ => Err(format!("unknown theme '{}', expected dark, light or retro", s)),
+        }
+    }
+}
+
+//Буквы шрифта 3 на 5 точек. Каждая строка буквы это три бита слева направо
+const GLYPHS: [(char, [u8; 5]); 20] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('C', [0b111, 0b100, 0b100, 0b100, 0b111]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('O', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+];
+
+//Чем рисуется клетка
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Shape {
+    //Клетка целиком
+    Block,
+    //Маленький квадрат в центре клетки. Так рисуется фрейм поля без стен
+    Dot,
+    //Круг вписанный в клетку
+    Ball,
+}
+
+//Форма и цвет точки игры
+fn style(point: &PointDto, wrapped: bool, theme: &Theme) -> (Shape, Rgb) {
+    let owner = point.owner.unwrap_or(0);
+    let other = |owner: usize| theme.others.get((owner - 1) % theme.others.len().max(1)).cloned();
+    match point.state_type {
+        PointDtoType::Frame if wrapped => (Shape::Dot, theme.dots),
+        PointDtoType::Frame => (Shape::Block, theme.frame),
+        PointDtoType::Wall => (Shape::Block, theme.wall),
+        PointDtoType::Head if owner > 0 => (Shape::Block, other(owner).map_or(theme.head, |c| c.0)),
+        PointDtoType::Tail if owner > 0 => (Shape::Block, other(owner).map_or(theme.tail, |c| c.1)),
+        PointDtoType::Head => (Shape::Block, theme.head),
+        PointDtoType::Tail => (Shape::Block, theme.tail),
+        PointDtoType::Food => (Shape::Ball, theme.food),
+        PointDtoType::BonusFood => (Shape::Ball, theme.bonus_food),
+        PointDtoType::PoisonFood => (Shape::Ball, theme.poison_food),
+        PointDtoType::GoldenFood => (Shape::Ball, theme.golden_food),
+    }
+}
+
+//Размеры картинки снимка: ширина, высота, высота строки со счетом и во сколько раз увеличен шрифт
+fn layout(snapshot: &GameSnapshot, cell: u32) -> (u32, u32, u32, u32) {
+    let columns = snapshot.points.iter().map(|p| p.x as u32 + 1).max().unwrap_or(1);
+    let rows = snapshot.points.iter().map(|p| p.y as u32 + 1).max().unwrap_or(1);
+    let scale = (cell / 4).max(1);
+    let header = 7 * scale;
+    (columns * cell, rows * cell + header, header, scale)
+}
+
+//Змейки рисуем поверх еды, а их головы поверх хвостов
+fn in_draw_order(snapshot: &GameSnapshot) -> Vec<&PointDto> {
+    let mut points: Vec<&PointDto> = snapshot.points.iter().collect();
+    points.sort_by_key(|p| match p.state_type {
+        PointDtoType::Head => 2,
+        PointDtoType::Tail => 1,
+        _ => 0,
+    });
+    points
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+//Картинка в памяти. Точки идут по строкам сверху вниз, по три байта на точку
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Picture {
+    //Картинка залитая одним цветом
+    pub fn new(width: u32, height: u32, color: Rgb) -> Picture {
+        let pixels = (0..width * height).flat_map(|_| color.iter().cloned()).collect();
+        Picture { width, height, pixels }
+    }
+
+    //Рисуем снимок игры. cell это размер клетки поля в точках
+    pub fn from_snapshot(snapshot: &GameSnapshot, cell: u32, theme: &Theme) -> Picture {
+        let cell = cell.max(1);
+        let (width, height, header, scale) = layout(snapshot, cell);
+        let rows = (height - header) / cell;
+        let wrapped = snapshot.topology == Topology::Wrapped;
+        let mut picture = Picture::new(width, height, theme.background);
+        picture.text(scale, scale, scale, &score_line(snapshot), theme.text);
+        for point in in_draw_order(snapshot) {
+            let x = point.x as u32 * cell;
+            let y = header + (rows - 1 - point.y as u32) * cell;
+            match style(point, wrapped, theme) {
+                (Shape::Block, color) => picture.rectangle(x, y, cell, cell, color),
+                (Shape::Dot, color) => {
+                    let size = (cell / 3).max(1);
+                    picture.rectangle(x + (cell - size) / 2, y + (cell - size) / 2, size, size, color)
+                }
+                (Shape::Ball, color) => picture.ball(x, y, cell, color),
+            }
+        }
+        picture
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgb {
+        let i = ((y * self.width + x) * 3) as usize;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    //Точки за краем картинки пропускаем
+    fn set(&mut self, x: u32, y: u32, color: Rgb) {
+        if x < self.width && y < self.height {
+            let i = ((y * self.width + x) * 3) as usize;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    fn rectangle(&mut self, x: u32, y: u32, width: u32, height: u32, color: Rgb) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.set(px, py, color);
+            }
+        }
+    }
+
+    //Круг вписанный в квадрат со стороной size
+    fn ball(&mut self, x: u32, y: u32, size: u32, color: Rgb) {
+        let radius = size as f32 / 2.0;
+        for py in 0..size {
+            for px in 0..size {
+                let dx = px as f32 + 0.5 - radius;
+                let dy = py as f32 + 0.5 - radius;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.set(x + px, y + py, color);
+                }
+            }
+        }
+    }
+
+    //Пишем строку шрифтом 3 на 5 увеличенным в scale раз. Неизвестные буквы остаются пробелами
+    fn text(&mut self, x: u32, y: u32, scale: u32, text: &str, color: Rgb) {
+        for (i, c) in text.chars().enumerate() {
+            let left = x + i as u32 * 4 * scale;
+            let rows = match GLYPHS.iter().find(|g| g.0 == c) {
+                Some(glyph) => glyph.1,
+                None if c == ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+                None => continue,
+            };
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.rectangle(left + column * scale, y + row as u32 * scale, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+//Тот же снимок в виде SVG. Размеры и цвета такие же как у Picture::from_snapshot
+pub fn snapshot_svg(snapshot: &GameSnapshot, cell: u32, theme: &Theme) -> String {
+    let cell = cell.max(1);
+    let (width, height, header, scale) = layout(snapshot, cell);
+    let rows = (height - header) / cell;
+    let wrapped = snapshot.topology == Topology::Wrapped;
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n", width, height);
+    svg += &format!("<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", width, height, hex(theme.background));
+    svg += &format!("<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" fill=\"{}\">{}</text>\n",
+                    scale, 6 * scale, 5 * scale, hex(theme.text), score_line(snapshot));
+    for point in in_draw_order(snapshot) {
+        let x = point.x as u32 * cell;
+        let y = header + (rows - 1 - point.y as u32) * cell;
+        svg += &match style(point, wrapped, theme) {
+            (Shape::Block, color) => format!("<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>\n", x, y, cell, hex(color)),
+            (Shape::Dot, color) => {
+                let size = (cell / 3).max(1);
+                format!("<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>\n",
+                        x + (cell - size) / 2, y + (cell - size) / 2, size, hex(color))
+            }
+            (Shape::Ball, color) => format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+                                            x as f32 + cell as f32 / 2.0, y as f32 + cell as f32 / 2.0, cell as f32 / 2.0, hex(color)),
+        };
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+//Как превратить запись игры в анимацию
+pub struct AnimationConfig {
+    //Кадров в секунду. Игра идет с той же скоростью что и при записи
+    pub fps: u32,
+    //Размер клетки поля в точках
+    pub cell: u32,
+    pub theme: Theme,
+    //С какого шага записи начинать и на каком закончить. Без конца анимация идет до конца записи
+    pub from: u64,
+    pub to: Option<u64>,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> AnimationConfig {
+        AnimationConfig { fps: 10, cell: 8, theme: Theme::default(), from: 0, to: None }
+    }
+}
+
+//Кадры анимации записи по одному. Каждый кадр это состояние игры через 1 / fps секунды после предыдущего.
+//Кадр рисуется только когда его просят, поэтому длинная запись не занимает память целиком
+pub struct ReplayFrames {
+    //Проигрыватель стоит на следующем кадре. Когда кадры закончились его нет
+    player: Option<ReplayPlayer>,
+    time_delta: f32,
+    cell: u32,
+    theme: Theme,
+}
+
+impl ReplayFrames {
+    //Отдаем игру на текущем кадре и переходим к следующему
+    fn next_with<T, F: FnOnce(&ReplayPlayer) -> T>(&mut self, f: F) -> Option<T> {
+        let player = self.player.take()?;
+        let value = f(&player);
+        //Игра могла закончиться раньше чем говорит запись
+        let over = matches!(player.controller.get_phase(), Phase::GameOver(_));
+        if !player.is_finished() && !over {
+            self.player = Some(player.update(self.time_delta, Vec::new()));
+        }
+        Some(value)
+    }
+}
+
+impl Iterator for ReplayFrames {
+    type Item = Picture;
+
+    fn next(&mut self) -> Option<Picture> {
+        let (cell, theme) = (self.cell, self.theme.clone());
+        self.next_with(|player| Picture::from_snapshot(&player.controller.snapshot(), cell, &theme))
+    }
+
+    //Считаем кадры без рисования. Игра все равно проигрывается до конца
+    fn count(mut self) -> usize {
+        let mut count = 0;
+        while self.next_with(|_| ()).is_some() {
+            count += 1;
+        }
+        count
+    }
+}
+
+//Кадры анимации записи с шага from до шага to
+pub fn replay_frames(replay: &Replay, config: &AnimationConfig) -> Result<ReplayFrames, Box<dyn Error>> {
+    if let Some(to) = config.to {
+        if config.from > to {
+            return Err(format!("the first tick {} is after the last tick {}", config.from, to).into());
+        }
+    }
+    //Укороченная запись сама остановится на последнем нужном шаге
+    let ticks = config.to.unwrap_or(replay.ticks).min(replay.ticks);
+    let replay = Replay { ticks, ..replay.clone() };
+    let player = ReplayPlayer::new(replay).seek(config.from);
+    Ok(ReplayFrames {
+        player: Some(player),
+        time_delta: 1.0 / config.fps.max(1) as f32,
+        cell: config.cell,
+        theme: config.theme.clone(),
+    })
+}
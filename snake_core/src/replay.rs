@@ -0,0 +1,211 @@
+//Application Layer. Запись и повтор игры ------------------------------------------
+
+/*
+Игра полностью определяется начальным положением, зерном еды и нажатиями игрока,
+поэтому запись хранит только их. Каждое нажатие помечено номером шага змейки
+на котором оно было сделано. При повторе игра двигается ровно по шагам,
+а не по времени кадров, поэтому повтор всегда совпадает с записанной игрой.
+*/
+
+use ai::AiLevel;
+use controller::GameController;
+use entities::{FoodConfig, Snake, SpeedCurve, Topology};
+use level::Level;
+use logic::{Command, Game, Phase};
+
+//Во сколько раз меняется скорость повтора при ускорении и замедлении
+const SPEED_FACTOR: f32 = 2.0;
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.0;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Нажатие игрока и шаг змейки на котором оно было сделано
+pub struct ReplayInput {
+    pub tick: u64,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+//Запись одной игры
+pub struct Replay {
+    //Фрейм, стены и еда уровня
+    pub level: Level,
+    pub topology: Topology,
+    pub food: FoodConfig,
+    pub speed: SpeedCurve,
+    //Начальные змейки игроков и кто из них управляется компьютером
+    pub snakes: Vec<Snake>,
+    pub bots: Vec<Option<AiLevel>>,
+    pub seed: u64,
+    pub inputs: Vec<ReplayInput>,
+    //Сколько шагов длилась игра
+    pub ticks: u64,
+}
+
+impl Replay {
+    //Начинаем запись игры которая только что началась
+    pub fn record(game: &Game) -> Replay {
+        let generator = &game.food_generator;
+        let level = Level {
+            width: game.frame.max_x + 1,
+            height: game.frame.max_y + 1,
+            walls: game.walls.clone(),
+            spawn: game.players[0].snake.head(),
+            direction: game.players[0].snake.direction.clone(),
+            food: generator.level_food.clone(),
+        };
+        Replay {
+            level,
+            topology: game.topology,
+            food: generator.config.clone(),
+            speed: game.speed,
+            snakes: game.players.iter().map(|p| p.snake.clone()).collect(),
+            bots: game.players.iter().map(|p| p.bot).collect(),
+            seed: game.seed,
+            inputs: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    //Запоминаем нажатие сделанное на текущем шаге
+    pub fn with_input(self, tick: u64, command: Command) -> Replay {
+        let mut replay = self;
+        replay.inputs.push(ReplayInput { tick, command });
+        replay
+    }
+
+    //Контроллер с игрой в том виде в котором она была в начале записи.
+    //Обратного отсчета нет, змейки сразу двигаются
+    pub fn start(&self) -> GameController {
+        let mut game = Game::from_level(&self.level, self.seed)
+            .with_topology(self.topology)
+            .with_food_config(self.food.clone())
+            .with_speed(self.speed);
+        for (i, snake) in self.snakes.iter().enumerate() {
+            game = if i == 0 {
+                game.with_snake(snake.clone())
+            } else {
+                game.with_player(snake.clone())
+            };
+        }
+        for (i, bot) in self.bots.iter().enumerate() {
+            game = game.with_bot(i, *bot);
+        }
+        let game = game.restart_with_seed(self.seed).with_phase(Phase::Playing);
+        GameController::from_game(game)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+//Команды зрителя во время повтора
+pub enum PlaybackCommand {
+    //Остановить повтор или продолжить его
+    Pause,
+    //Ускорить или замедлить повтор в SPEED_FACTOR раз
+    Faster,
+    Slower,
+    //Перейти к заданному шагу
+    Seek(u64),
+}
+
+#[derive(Debug, Clone)]
+//Проигрыватель записи. Двигает игру по шагам и подает в нее записанные нажатия
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub controller: GameController,
+    //Номер следующего нажатия которое еще не подано в игру
+    next: usize,
+    paused: bool,
+    speed: f32,
+    total_time: f32,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        let controller = replay.start();
+        ReplayPlayer { replay, controller, next: 0, paused: false, speed: 1.0, total_time: 0f32 }
+    }
+
+    //Шаг записи на котором сейчас игра
+    pub fn tick(&self) -> u64 {
+        self.controller.game.ticks
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    //Во сколько раз повтор быстрее записанной игры
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    //Запись доиграна до конца
+    pub fn is_finished(&self) -> bool {
+        self.tick() >= self.replay.ticks
+    }
+
+    //Один шаг записи: подаем нажатия этого шага и двигаем змейки
+    pub fn advance(self) -> ReplayPlayer {
+        let mut player = self;
+        let tick = player.tick();
+        let mut commands = Vec::new();
+        while let Some(input) = player.replay.inputs.get(player.next).filter(|i| i.tick <= tick) {
+            commands.push(input.command.clone());
+            player.next += 1;
+        }
+        player.controller = player.controller.advance(commands);
+        player
+    }
+
+    //Переходим к заданному шагу. Назад двигаться игра не умеет,
+    //поэтому для перехода назад запись проигрывается заново с начала
+    pub fn seek(self, tick: u64) -> ReplayPlayer {
+        let tick = tick.min(self.replay.ticks);
+        let mut player = if tick < self.tick() {
+            ReplayPlayer { controller: self.replay.start(), next: 0, ..self }
+        } else {
+            self
+        };
+        while player.tick() < tick && !player.is_finished() {
+            let before = player.tick();
+            player = player.advance();
+            //Игра закончилась раньше чем говорит запись
+            if player.tick() == before {
+                break;
+            }
+        }
+        ReplayPlayer { total_time: 0f32, ..player }
+    }
+
+    //Обрабатываем команды зрителя и двигаем повтор на столько шагов сколько успело пройти времени
+    pub fn update(self, time_delta: f32, commands: Vec<PlaybackCommand>) -> ReplayPlayer {
+        let mut player = commands.into_iter().fold(self, ReplayPlayer::handle_command);
+        if player.paused || player.is_finished() {
+            return player;
+        }
+        player.total_time += time_delta * player.speed;
+        loop {
+            let interval = player.controller.get_tick_interval();
+            if player.total_time < interval || player.is_finished() {
+                break;
+            }
+            player.total_time -= interval;
+            let tick = player.tick();
+            player = player.advance();
+            if player.tick() == tick {
+                break;
+            }
+        }
+        player
+    }
+
+    fn handle_command(self, command: PlaybackCommand) -> ReplayPlayer {
+        match command {
+            PlaybackCommand::Pause => ReplayPlayer { paused: !self.paused, ..self },
+            PlaybackCommand::Faster => ReplayPlayer { speed: (self.speed * SPEED_FACTOR).min(MAX_SPEED), ..self },
+            PlaybackCommand::Slower => ReplayPlayer { speed: (self.speed / SPEED_FACTOR).max(MIN_SPEED), ..self },
+            PlaybackCommand::Seek(tick) => self.seek(tick),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//Application Layer. Игры без окна для проверки компьютерных игроков -----------------
+
+use ai::{self, AiLevel};
+use entities::Direction;
+use logic::{DeathCause, Game, Outcome, Phase};
+
+//Кто управляет змейкой первого игрока в игре без окна
+pub trait Policy {
+    //Куда повернуть змейку игрока на следующем шаге. Ничего если ехать прямо
+    fn choose(&mut self, game: &Game, player: usize) -> Option<Direction>;
+}
+
+//Встроенный компьютерный игрок заданного уровня
+pub struct BuiltinPolicy(pub AiLevel);
+
+impl Policy for BuiltinPolicy {
+    fn choose(&mut self, game: &Game, player: usize) -> Option<Direction> {
+        ai::choose_direction(game, player, self.0)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+//Итоги одной игры первого игрока
+pub struct GameReport {
+    pub seed: u64,
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    //Чем кончилась игра: причина гибели, cleared, won, draw
+    //или timeout если игра не закончилась за отведенное число шагов
+    pub cause: String,
+}
+
+impl GameReport {
+    pub fn new(game: &Game) -> GameReport {
+        let cause = match game.phase {
+            Phase::GameOver(ref summary) => match (summary.outcome, game.players[0].death) {
+                (_, Some(DeathCause::Frame)) => "frame",
+                (_, Some(DeathCause::Wall)) => "wall",
+                (_, Some(DeathCause::Tail)) => "tail",
+                (_, Some(DeathCause::Snake)) => "snake",
+                (Outcome::BoardCleared, None) => "cleared",
+                (Outcome::Draw, None) => "draw",
+                (_, None) => "won",
+            },
+            _ => "timeout",
+        };
+        let player = &game.players[0];
+        GameReport {
+            seed: game.seed,
+            score: player.score,
+            length: player.snake.points.len(),
+            ticks: game.ticks,
+            cause: cause.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+//Сводка по всем играм
+pub struct Statistics {
+    pub games: usize,
+    pub mean_score: f64,
+    pub min_score: usize,
+    pub max_score: usize,
+    pub mean_length: f64,
+    pub mean_ticks: f64,
+    //Сколько игр закончилось каждой причиной, по алфавиту
+    pub causes: Vec<(String, usize)>,
+}
+
+impl Statistics {
+    pub fn new(reports: &[GameReport]) -> Statistics {
+        if reports.is_empty() {
+            return Statistics::default();
+        }
+        let count = reports.len() as f64;
+        let mut causes: Vec<(String, usize)> = Vec::new();
+        for report in reports {
+            match causes.iter_mut().find(|c| c.0 == report.cause) {
+                Some(cause) => cause.1 += 1,
+                None => causes.push((report.cause.clone(), 1)),
+            }
+        }
+        causes.sort();
+        Statistics {
+            games: reports.len(),
+            mean_score: reports.iter().map(|r| r.score as f64).sum::<f64>() / count,
+            min_score: reports.iter().map(|r| r.score).min().unwrap_or(0),
+            max_score: reports.iter().map(|r| r.score).max().unwrap_or(0),
+            mean_length: reports.iter().map(|r| r.length as f64).sum::<f64>() / count,
+            mean_ticks: reports.iter().map(|r| r.ticks as f64).sum::<f64>() / count,
+            causes,
+        }
+    }
+}
+
+//Играем одну игру до конца или до max_ticks шагов. Время каждого обновления одинаковое,
+//поэтому результат зависит только от зерна игры и от решений политики.
+//Политику спрашиваем один раз за шаг когда у змейки не осталось поворотов в очереди.
+//Без времени игра не двигается, поэтому при time_delta <= 0 игра возвращается как есть
+pub fn simulate<P: Policy>(game: Game, policy: &mut P, time_delta: f32, max_ticks: u64) -> Game {
+    let mut game = game.with_phase(Phase::Playing);
+    let mut decided = None;
+    while time_delta > 0.0 && game.phase == Phase::Playing && game.ticks < max_ticks {
+        if decided != Some(game.ticks) && game.players[0].turns.is_empty() {
+            if let Some(direction) = policy.choose(&game, 0) {
+                game = game.handle_input(0, direction);
+            }
+            decided = Some(game.ticks);
+        }
+        game = game.update(time_delta);
+    }
+    game
+}
@@ -0,0 +1,1339 @@
+//Старые тесты сохранены как были: имена переменных в camelCase и лишний mut
+#![allow(clippy::bool_assert_comparison, non_snake_case, unused_mut)]
+
+use super::*;
+
+#[test]
+fn test_point_intesect() {
+    let p1 = Point{x:1,y:1};
+    let p2 = Point{x:1,y:1};
+    let p  = Point{x:2,y:1};
+    assert_eq!(true,p2.intersects(&p1));
+    assert_eq!(true,p1.intersects(&p2));
+    assert_eq!(false,p1.intersects(&p));
+    assert_eq!(false,p.intersects(&p1));
+}
+
+#[test]
+fn test_frame_intersects() {
+    let f1 = Frame {
+        min_x: 1,
+        min_y: 1,
+        max_x: 2,
+        max_y: 2,
+    };
+    let p1 = Point{x:1,y:1};
+    let p2 = Point{x:3,y:3};
+
+    assert_eq!(true, f1.intersects(&p1));
+    assert_eq!(false, f1.intersects(&p2));
+}
+
+#[test]
+fn test_snake_new(){
+    let snake = Snake::new(1,2);
+    assert_eq!(1, snake.start_x);
+    assert_eq!(2, snake.start_y);
+    assert_eq!(Direction::Right, snake.direction);
+}
+
+#[test]
+fn test_snake_grow(){
+    let snake = Snake::new(1,2);
+    let old = snake.points.clone();
+    let newSnake = snake.grow();
+    let new = newSnake.points.clone();
+    assert_eq!(1, new.len() - old.len());
+}
+
+#[test]
+fn test_snake_reset(){
+    let snake = Snake::new(1,2).grow().reset();
+    let snake2 = Snake::new(1,2);
+    assert_eq!(snake,snake2);
+}
+
+#[test]
+fn test_snake_turn() {
+let snake = Snake::new(1,2);
+    let snake2 = Snake::new(1,2).turn(Direction::Top);
+    assert_eq!(snake.direction, Direction::default());
+    assert_eq!(snake2.direction, Direction::Top);
+    assert_ne!(snake2.direction, snake.direction);
+}
+
+#[test]
+fn test_snake_try_eat(){
+    let snake = Snake::new(1,2);
+    let point = snake.head();
+    let point2 = Point{x:100, y:100};
+
+    let len1 = snake.points.len();
+    let (snake, _) = snake.try_eat(&point2);
+    let len2 = snake.points.len();
+    let len3 = snake.try_eat(&point).0.points.len();
+    assert_eq!(len1,len2);
+    assert_eq!(1, len3 - len2);
+}
+
+#[test]
+fn test_snake_try_intersect_frame_true(){
+    let snake = Snake::new(1,2).grow();
+    let snake2 = Snake::new(1,2);
+    let head = snake.head();
+    let frame = Frame{
+        min_x: 1,
+        min_y: 1,
+        max_x: head.x,
+        max_y: head.y
+    };
+    let snake = snake.try_intersect_frame(&frame);
+    assert_eq!(snake,snake2);
+}
+
+#[test]
+fn test_snake_try_intersect_frame_false(){
+    let snake = Snake::new(1,2).grow();
+    let snake2 = Snake::new(1,2);
+    let frame = Frame{
+        min_x: 1,
+        min_y: 1,
+        max_x: 255,
+        max_y: 255
+    };
+    let snake = snake.try_intersect_frame(&frame);
+    assert_ne!(snake,snake2);
+    assert_eq!(1, snake.points.len() - snake2.points.len());
+}
+
+#[test]
+fn test_try_intersect_tail_true(){
+    let mut snake = Snake::new(1,2).grow();
+    let snake2 = Snake::new(1,2);
+    let head = snake.head();
+    snake.points.push_back(head);
+    let snake = snake.try_intersect_tail();
+    assert_eq!(snake, snake2);
+}
+
+#[test]
+fn test_try_intersect_tail_false(){
+    let mut snake = Snake::new(1,2).grow();
+    let snake2 = Snake::new(1,2);
+    let snake = snake.try_intersect_tail();
+    assert_ne!(snake,snake2);
+    assert_eq!(1, snake.points.len() - snake2.points.len());
+}
+
+#[test]
+fn test_mov_snake_should_move_snake_to_1_on_direction(){
+    let snake = Snake::new(1,2);
+    let frame = Frame{min_x:0,min_y:0,max_x:30,max_y:30};
+    let movedSnake = snake.clone().move_snake(&frame, Topology::Walled);
+    let head = snake.head();
+    let movedHead = movedSnake.head();
+
+    assert_eq!(snake.direction, Direction::Right);
+    assert_eq!(head.y, movedHead.y);
+    assert_eq!(head.x, movedHead.x -1);
+}
+
+#[test]
+fn generate_should_generate_point_in_frame(){
+    let frame = Frame {min_x:0,min_y:0,max_x:5,max_y:5};
+    let generator = FoodGenerator::new(frame.clone(), 42);
+    let grid = Grid::new(&frame);
+    let (generator, point1) = generator.generate(&grid);
+    let (_, point2) = generator.generate(&grid);
+    let (point1, point2) = (point1.unwrap().point, point2.unwrap().point);
+    assert_ne!(point1,point2);
+    for p in [point1, point2].iter() {
+        assert!(p.x > frame.min_x && p.x < frame.max_x);
+        assert!(p.y > frame.min_y && p.y < frame.max_y);
+    }
+}
+
+#[test]
+fn generate_should_repeat_food_for_same_seed(){
+    let frame = Frame {min_x:0,min_y:0,max_x:30,max_y:30};
+    let grid = Grid::new(&frame);
+    let mut first = FoodGenerator::new(frame.clone(), 7);
+    let mut second = FoodGenerator::new(frame, 7);
+    for _ in 0..20 {
+        let (g1, p1) = first.generate(&grid);
+        let (g2, p2) = second.generate(&grid);
+        assert_eq!(p1, p2);
+        first = g1;
+        second = g2;
+    }
+}
+
+#[test]
+fn game_with_same_seed_and_input_should_be_same(){
+    let inputs = [Direction::Bottom, Direction::Left, Direction::Top, Direction::Right];
+    let play = |seed: u64| {
+        let mut game = Game::new(10, 10, seed);
+        let mut foods = Vec::new();
+        for i in 0..200 {
+            if i % 7 == 0 {
+                game = game.handle_input(0, inputs[(i / 7) % inputs.len()].clone());
+            }
+            game = game.update(0.031);
+            foods.push(game.foods.clone());
+        }
+        (foods, game.players[0].snake.clone(), game.players[0].score)
+    };
+    assert_eq!(play(3), play(3));
+}
+
+#[test]
+fn move_snake_should_wrap_around_frame(){
+    let frame = Frame{min_x:0,min_y:0,max_x:5,max_y:5};
+    let snake = Snake::new(3,2).move_snake(&frame, Topology::Wrapped);
+    assert_eq!(Point{x:0,y:4}, snake.head());
+    let snake = snake.turn(Direction::Top).move_snake(&frame, Topology::Wrapped);
+    let snake = snake.move_snake(&frame, Topology::Wrapped);
+    let snake = snake.move_snake(&frame, Topology::Wrapped);
+    let snake = snake.move_snake(&frame, Topology::Wrapped);
+    assert_eq!(Point{x:0,y:0}, snake.head());
+    let snake = snake.move_snake(&frame, Topology::Wrapped);
+    assert_eq!(Point{x:0,y:5}, snake.head());
+    let snake = snake.turn(Direction::Left).move_snake(&frame, Topology::Wrapped);
+    assert_eq!(Point{x:5,y:5}, snake.head());
+}
+
+#[test]
+fn wrapped_game_should_not_reset_snake_on_frame(){
+    let game = Game::new(10, 10, 1).with_topology(Topology::Wrapped);
+    let mut game = game.handle_input(0, Direction::Top);
+    for _ in 0..25 {
+        let head = game.players[0].snake.head();
+        game = game.update(0.031);
+        assert_eq!(head.x, game.players[0].snake.head().x);
+        assert_eq!((head.y + 10) % 11, game.players[0].snake.head().y);
+    }
+}
+
+#[test]
+fn test_snake_try_intersect_walls(){
+    let snake = Snake::new(1,2).grow();
+    let head = snake.head();
+    let snake2 = snake.clone().try_intersect_walls(&[Point{x:10,y:10}]);
+    assert_eq!(snake, snake2);
+    let snake3 = snake.try_intersect_walls(&[head]);
+    assert_eq!(Snake::new(1,2), snake3);
+}
+
+#[test]
+fn generate_should_not_put_food_on_walls(){
+    let frame = Frame {min_x:0,min_y:0,max_x:4,max_y:4};
+    let walls = vec![Point{x:1,y:1}, Point{x:2,y:1}, Point{x:3,y:1}, Point{x:1,y:2}, Point{x:3,y:2},
+                     Point{x:1,y:3}, Point{x:2,y:3}, Point{x:3,y:3}];
+    let grid = walls.iter().fold(Grid::new(&frame), |grid, w| grid.set_wall(w, true));
+    let mut generator = FoodGenerator::new(frame, 5).with_walls(walls);
+    for _ in 0..20 {
+        let (g, food) = generator.generate(&grid);
+        assert_eq!(Point{x:2,y:2}, food.unwrap().point);
+        generator = g;
+    }
+}
+
+#[test]
+fn get_state_should_report_walls(){
+    let walls = vec![Point{x:3,y:3}, Point{x:4,y:3}];
+    let controller = GameController::with_seed(&Level::default(), 1).with_walls(walls);
+    let reported: Vec<PointDto> = controller.get_state().into_iter()
+        .filter(|p| p.state_type == PointDtoType::Wall)
+        .collect();
+    assert_eq!(2, reported.len());
+    assert!(reported.iter().any(|p| p.x == 4 && p.y == 3));
+}
+
+#[test]
+fn test_snake_spawn(){
+    let snake = Snake::spawn(Point{x:5,y:5}, Direction::Top);
+    assert_eq!(Point{x:5,y:5}, snake.head());
+    assert_eq!(Some(&Point{x:5,y:7}), snake.points.back());
+    let reset = snake.clone().turn(Direction::Left).grow().reset();
+    assert_eq!(snake, reset);
+}
+
+#[test]
+fn level_parse_should_read_map(){
+    let text = "#######\n#  F  #\n#  S>##\n#######\n";
+    let level = Level::parse(text).unwrap();
+    assert_eq!(7, level.width);
+    assert_eq!(4, level.height);
+    assert_eq!(vec![Point{x:5,y:1}], level.walls);
+    assert_eq!(Point{x:3,y:1}, level.spawn);
+    assert_eq!(Direction::Right, level.direction);
+    assert_eq!(vec![Point{x:3,y:2}], level.food);
+}
+
+#[test]
+fn level_parse_should_report_line_and_column(){
+    let text = "#####\n# S #\n#  x#\n#####";
+    let error = Level::parse(text).unwrap_err();
+    assert_eq!((3, 4), (error.line, error.column));
+    let error = Level::parse("#####\n#   #\n#####").unwrap_err();
+    assert_eq!("line 1, column 1: level has no snake spawn 'S'", error.to_string());
+    let error = Level::parse("######\n#S   #\n######").unwrap_err();
+    assert_eq!((2, 2), (error.line, error.column));
+}
+
+#[test]
+fn game_from_level_should_use_level(){
+    let text = "########\n#      #\n#      #\n#    S #\n#    v #\n#F#    #\n########";
+    let game = Game::from_level(&Level::parse(text).unwrap(), 1);
+    assert_eq!(Point{x:1,y:1}, game.foods[0].point);
+    assert_eq!(vec![Point{x:2,y:1}], game.walls);
+    assert_eq!(Point{x:5,y:3}, game.players[0].snake.head());
+    assert_eq!(Direction::Top, game.players[0].snake.direction);
+    assert_eq!(7, game.frame.max_x);
+}
+
+#[test]
+fn test_snake_shrink(){
+    let snake = Snake::new(1,2).grow().grow();
+    assert_eq!(3, snake.clone().shrink(2).points.len());
+    assert_eq!(1, snake.clone().shrink(10).points.len());
+    assert_eq!(snake.head(), snake.clone().shrink(10).head());
+}
+
+#[test]
+fn game_should_keep_configured_food_count(){
+    let config = FoodConfig{count: 5, ..FoodConfig::mixed(5)};
+    let mut game = Game::new(20, 20, 9).with_food_config(config);
+    for i in 0..300 {
+        if i % 10 == 0 {
+            let turns = [Direction::Bottom, Direction::Left, Direction::Top, Direction::Right];
+            game = game.handle_input(0, turns[(i / 10) % 4].clone());
+        }
+        game = game.update(0.031);
+        assert_eq!(5, game.foods.len());
+        for (i, f) in game.foods.iter().enumerate() {
+            assert!(game.foods.iter().skip(i + 1).all(|o| o.point != f.point));
+        }
+    }
+}
+
+#[test]
+fn food_kinds_should_apply_effects(){
+    let config = FoodConfig::mixed(1);
+    let eat = |kind: FoodKind| {
+        let game = Game::new(20, 20, 1).with_food_config(config.clone());
+        let head = game.players[0].snake.clone().move_snake(&game.frame, game.topology).head();
+        let game = game.with_foods(vec![Food{point: head, kind}]);
+        let length = game.players[0].snake.points.len();
+        let game = game.update(0.031);
+        (game.players[0].snake.points.len() as isize - length as isize, game.players[0].score)
+    };
+    assert_eq!((1, 1), eat(FoodKind::Normal));
+    assert_eq!((1, config.bonus_points), eat(FoodKind::Bonus));
+    assert_eq!((-2, 0), eat(FoodKind::Poison));
+    assert_eq!((3, 1), eat(FoodKind::Golden));
+}
+
+#[test]
+fn get_state_should_report_food_kinds(){
+    let mut controller = GameController::with_seed(&Level::default(), 1);
+    controller.game = controller.game.with_foods(vec![Food{point: Point{x:1,y:1}, kind: FoodKind::Poison},
+                                 Food{point: Point{x:2,y:1}, kind: FoodKind::Golden}]);
+    let state = controller.get_state();
+    assert!(state.contains(&PointDto{x:1, y:1, state_type: PointDtoType::PoisonFood, owner: None}));
+    assert!(state.contains(&PointDto{x:2, y:1, state_type: PointDtoType::GoldenFood, owner: None}));
+}
+
+#[test]
+fn speed_curve_should_speed_up_and_stop_at_min(){
+    let fixed = SpeedCurve::Fixed{interval: 0.1};
+    assert_eq!(0.1, fixed.interval(100, 100));
+    let by_length = SpeedCurve::ByLength{start: 0.2, step: 0.01, min: 0.05};
+    assert!(by_length.interval(3, 0) > by_length.interval(4, 0));
+    assert_eq!(0.05, by_length.interval(1000, 0));
+    let by_score = SpeedCurve::ByScore{start: 0.2, step: 0.01, min: 0.05};
+    assert_eq!(by_score.interval(3, 0), by_score.interval(10, 0));
+    assert!(by_score.interval(0, 0) > by_score.interval(0, 1));
+}
+
+#[test]
+fn game_should_move_by_tick_interval(){
+    let game = Game::new(20, 20, 1).with_speed(SpeedCurve::Fixed{interval: 0.1});
+    let head = game.players[0].snake.head();
+    let game = game.update(0.05);
+    assert_eq!(head, game.players[0].snake.head());
+    let game = game.update(0.06);
+    assert_ne!(head, game.players[0].snake.head());
+    let controller = GameController::with_seed(&Level::default(), 1)
+        .with_speed(Difficulty::Easy.speed_curve());
+    assert_eq!(0.20, controller.get_tick_interval());
+    assert_eq!(Ok(Difficulty::Hard), "hard".parse());
+}
+
+#[test]
+fn game_should_end_with_summary_on_death(){
+    let game = Game::new(10, 10, 1);
+    let mut game = game.handle_input(0, Direction::Top);
+    for _ in 0..20 {
+        game = game.update(0.031);
+    }
+    match game.phase.clone() {
+        Phase::GameOver(summary) => {
+            assert_eq!(Outcome::Died(DeathCause::Frame), summary.outcome);
+            assert_eq!(0, game.players[0].snake.head().y);
+            assert_eq!(summary.ticks, game.ticks);
+        }
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+    let head = game.players[0].snake.head();
+    let game = game.update(0.031).handle_command(Command::Turn(0, Direction::Left)).update(0.031);
+    assert_eq!(head, game.players[0].snake.head());
+    let game = game.handle_command(Command::Confirm);
+    assert_eq!(Phase::Countdown{remaining: COUNTDOWN_SECONDS}, game.phase);
+    assert_eq!(Snake::new(5, 5), game.players[0].snake);
+    assert_eq!(0, game.players[0].score);
+}
+
+#[test]
+fn phases_should_take_own_commands(){
+    let game = Game::new(10, 10, 1).with_phase(Phase::Title);
+    let game = game.update(10.0).handle_command(Command::Turn(0, Direction::Top));
+    assert_eq!(Phase::Title, game.phase);
+    assert_eq!(Direction::Right, game.players[0].snake.direction);
+    let game = game.handle_command(Command::Confirm).update(1.0);
+    assert_eq!(Phase::Countdown{remaining: COUNTDOWN_SECONDS - 1.0}, game.phase);
+    let game = game.update(2.5);
+    assert_eq!(Phase::Playing, game.phase);
+    let game = game.handle_command(Command::Pause);
+    assert_eq!(Phase::Paused, game.phase);
+    let head = game.players[0].snake.head();
+    let game = game.update(1.0);
+    assert_eq!(head, game.players[0].snake.head());
+    let game = game.handle_command(Command::Pause);
+    assert_eq!(Phase::Playing, game.phase);
+}
+
+#[test]
+fn paused_game_should_step_one_tick(){
+    let game = Game::new(20, 20, 1).update(0.02).handle_command(Command::Pause);
+    let head = game.players[0].snake.head();
+    let game = game.update(5.0);
+    assert_eq!(head, game.players[0].snake.head());
+    assert_eq!(0.02, game.total_time);
+    let game = game.handle_command(Command::Step);
+    assert_eq!(Phase::Paused, game.phase);
+    assert_eq!(head.x + 1, game.players[0].snake.head().x);
+    assert_eq!(1, game.ticks);
+    let game = game.handle_command(Command::Step).handle_command(Command::Step);
+    assert_eq!(head.x + 3, game.players[0].snake.head().x);
+    assert_eq!(0.02, game.total_time);
+    let game = game.handle_command(Command::Pause).handle_command(Command::Step);
+    assert_eq!(Phase::Playing, game.phase);
+    assert_eq!(3, game.ticks);
+}
+
+#[test]
+fn handle_input_should_drop_reversal_and_same_direction(){
+    let game = Game::new(20, 20, 1);
+    let head = game.players[0].snake.head();
+    let game = game.handle_input(0, Direction::Left).handle_input(0, Direction::Right);
+    assert!(game.players[0].turns.is_empty());
+    let game = game.update(0.031);
+    assert_eq!(Phase::Playing, game.phase);
+    assert_eq!(Point{x: head.x + 1, y: head.y}, game.players[0].snake.head());
+}
+
+#[test]
+fn quick_turns_should_take_effect_on_next_ticks(){
+    let game = Game::new(20, 20, 1);
+    let head = game.players[0].snake.head();
+    let game = game.handle_input(0, Direction::Top).handle_input(0, Direction::Left).handle_input(0, Direction::Left);
+    assert_eq!(2, game.players[0].turns.len());
+    let game = game.update(0.031);
+    assert_eq!(Point{x: head.x, y: head.y - 1}, game.players[0].snake.head());
+    let game = game.update(0.031);
+    assert_eq!(Point{x: head.x - 1, y: head.y - 1}, game.players[0].snake.head());
+    assert_eq!(Phase::Playing, game.phase);
+}
+
+#[test]
+fn turn_queue_should_be_bounded(){
+    let mut game = Game::new(20, 20, 1);
+    for d in [Direction::Top, Direction::Left, Direction::Bottom, Direction::Right, Direction::Top].iter() {
+        game = game.handle_input(0, d.clone());
+    }
+    assert_eq!(MAX_QUEUED_TURNS, game.players[0].turns.len());
+}
+
+#[test]
+fn move_snake_should_not_overflow_at_coordinate_limits(){
+    let frame = Frame{min_x:0,min_y:0,max_x:Coord::MAX,max_y:Coord::MAX};
+    let snake = Snake::spawn(Point{x:0,y:5}, Direction::Left);
+    let moved = snake.clone().move_snake(&frame, Topology::Walled);
+    assert_eq!(Point{x:0,y:5}, moved.head());
+    assert!(moved.intersects_tail());
+    let snake = Snake::spawn(Point{x:Coord::MAX,y:Coord::MAX}, Direction::Bottom);
+    let moved = snake.move_snake(&frame, Topology::Walled);
+    assert_eq!(Point{x:Coord::MAX,y:Coord::MAX}, moved.head());
+}
+
+#[test]
+fn game_should_support_large_boards(){
+    let game = Game::new(1000, 1000, 4).with_food_config(FoodConfig{count: 10, ..FoodConfig::default()});
+    assert_eq!(Point{x:502,y:502}, game.players[0].snake.head());
+    assert!(game.foods.iter().all(|f| f.point.x < 1000 && f.point.y < 1000));
+    let mut game = game.handle_input(0, Direction::Top);
+    for _ in 0..600 {
+        game = game.update(0.031);
+    }
+    match game.phase {
+        Phase::GameOver(summary) => assert_eq!(Outcome::Died(DeathCause::Frame), summary.outcome),
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+}
+
+#[test]
+fn generate_should_use_only_free_cells(){
+    let frame = Frame {min_x:0,min_y:0,max_x:4,max_y:4};
+    let mut grid = Grid::new(&frame);
+    for y in 1..4 {
+        for x in 1..4 {
+            if (x, y) != (3, 1) {
+                grid = grid.add_snake(&Point{x, y});
+            }
+        }
+    }
+    let mut generator = FoodGenerator::new(frame, 5).with_fixed_food(vec![Point{x:1,y:1}]);
+    for _ in 0..10 {
+        let (g, food) = generator.generate(&grid);
+        assert_eq!(Some(Point{x:3,y:1}), food.map(|f| f.point));
+        generator = g;
+    }
+    let grid = grid.set_food(&Point{x:3,y:1}, true);
+    let (_, food) = generator.generate(&grid);
+    assert_eq!(None, food);
+}
+
+#[test]
+fn filling_the_board_should_be_a_victory(){
+    //Поле два на два. Змейка длиной три съедает еду в последней клетке,
+    //но выросший хвост остается на месте, поэтому свободная клетка появляется позади
+    let snake = Snake {
+        direction: Direction::Left,
+        points: vec![Point{x:2,y:2}, Point{x:2,y:1}, Point{x:1,y:1}].into_iter().collect(),
+        ..Snake::default()
+    };
+    let game = Game::new(3, 3, 1)
+        .with_snake(snake)
+        .with_foods(vec![Food{point: Point{x:1,y:2}, kind: FoodKind::Normal}]);
+    let game = game.update(0.031);
+    assert_eq!(Phase::Playing, game.phase);
+    assert_eq!(3, game.grid.snake_cells());
+    assert_eq!(vec![Point{x:1,y:1}], game.foods.iter().map(|f| f.point.clone()).collect::<Vec<_>>());
+    let game = game.handle_input(0, Direction::Top).update(0.031);
+    assert_eq!(4, game.grid.snake_cells());
+    match game.phase {
+        Phase::GameOver(summary) => {
+            assert_eq!(Outcome::BoardCleared, summary.outcome);
+            assert_eq!(2, summary.players[0].score);
+            assert_eq!(5, summary.players[0].length);
+        }
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+}
+
+#[test]
+fn growth_should_not_clear_the_board_while_cells_are_free(){
+    //Пять свободных клеток в ряд. Золотая еда удлиняет змейку на шесть точек в одной клетке
+    let game = Game::new(2, 6, 1)
+        .with_food_config(FoodConfig { golden_growth: 6, ..FoodConfig::default() })
+        .with_snake(Snake::spawn(Point{x:1,y:1}, Direction::Right).shrink(2))
+        .with_foods(vec![Food{point: Point{x:2,y:1}, kind: FoodKind::Golden}]);
+    let game = game.update(0.031);
+    assert_eq!(7, game.players[0].snake.points.len());
+    assert_eq!(1, game.grid.snake_cells());
+    assert_eq!(Phase::Playing, game.phase);
+}
+
+#[test]
+fn grid_should_follow_the_snake(){
+    let game = Game::new(20, 20, 1).with_walls(vec![Point{x:3,y:3}]);
+    let tail = game.players[0].snake.points.back().cloned().unwrap();
+    let game = game.update(0.031);
+    let head = game.players[0].snake.head();
+    assert_eq!(1, game.grid.cell(&head).snake);
+    assert_eq!(0, game.grid.cell(&tail).snake);
+    assert!(game.grid.cell(&Point{x:3,y:3}).wall);
+    for f in game.foods.iter() {
+        assert!(game.grid.cell(&f.point).food);
+    }
+    let rebuilt = game.clone().with_grid();
+    assert_eq!(rebuilt.grid, game.grid);
+}
+
+#[test]
+fn grid_should_keep_playable_cells_of_the_level(){
+    let game = Game::new(2, 6, 1);
+    assert_eq!(5, game.grid.playable_cells());
+    let game = game.with_walls(vec![Point{x:4,y:1}]);
+    assert_eq!(4, game.grid.playable_cells());
+    let game = game.with_topology(Topology::Wrapped);
+    assert_eq!(game.food_generator.playable_cells(), game.grid.playable_cells());
+    assert_eq!(3 * 7 - 1, game.grid.playable_cells());
+}
+
+#[test]
+fn grid_should_count_stacked_snake_points(){
+    let grid = Grid::new(&Frame{min_x:0,min_y:0,max_x:2,max_y:2});
+    let p = Point{x:1,y:1};
+    let grid = grid.add_snake(&p).add_snake(&p);
+    assert_eq!(2, grid.cell(&p).snake);
+    let grid = grid.remove_snake(&p);
+    assert_eq!(1, grid.cell(&p).snake);
+    assert!(!grid.is_free(&p));
+    assert!(grid.is_free(&Point{x:5,y:5}));
+}
+
+fn two_players(first: Snake, second: Snake) -> Game {
+    Game::new(20, 20, 1)
+        .with_snake(first)
+        .with_player(second)
+}
+
+#[test]
+fn head_to_head_should_be_a_draw(){
+    let game = two_players(Snake::spawn(Point{x:5,y:10}, Direction::Right), Snake::spawn(Point{x:7,y:10}, Direction::Left));
+    let game = game.update(0.031);
+    match game.phase {
+        Phase::GameOver(summary) => {
+            assert_eq!(Outcome::Draw, summary.outcome);
+            assert_eq!(Some(DeathCause::Snake), summary.players[0].death);
+            assert_eq!(Some(DeathCause::Snake), summary.players[1].death);
+        }
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+}
+
+#[test]
+fn hitting_another_snake_should_let_it_win(){
+    let game = two_players(Snake::spawn(Point{x:10,y:10}, Direction::Right), Snake::spawn(Point{x:10,y:8}, Direction::Bottom));
+    let game = game.update(0.031);
+    assert_eq!(Phase::Playing, game.phase);
+    let game = game.update(0.031);
+    match game.phase {
+        Phase::GameOver(summary) => {
+            assert_eq!(Outcome::Won(0), summary.outcome);
+            assert_eq!(None, summary.players[0].death);
+            assert_eq!(Some(DeathCause::Snake), summary.players[1].death);
+        }
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+}
+
+#[test]
+fn human_losing_to_a_bot_should_report_the_death(){
+    let game = two_players(Snake::spawn(Point{x:10,y:8}, Direction::Bottom), Snake::spawn(Point{x:10,y:10}, Direction::Right))
+        .with_bot(1, Some(AiLevel::Greedy));
+    let game = game.update(0.031).update(0.031);
+    match game.phase {
+        Phase::GameOver(summary) => {
+            assert_eq!(Outcome::Died(DeathCause::Snake), summary.outcome);
+            assert_eq!(None, summary.players[1].death);
+        }
+        phase => panic!("unexpected phase {:?}", phase),
+    }
+}
+
+#[test]
+fn turns_should_go_to_their_player(){
+    let game = two_players(Snake::spawn(Point{x:5,y:5}, Direction::Right), Snake::spawn(Point{x:15,y:15}, Direction::Left));
+    let game = game.handle_command(Command::Turn(1, Direction::Top))
+        .handle_command(Command::Turn(2, Direction::Top));
+    assert!(game.players[0].turns.is_empty());
+    assert_eq!(vec![Direction::Top], game.players[1].turns.iter().cloned().collect::<Vec<_>>());
+    let game = game.update(0.031);
+    assert_eq!(Direction::Right, game.players[0].snake.direction);
+    assert_eq!(Direction::Top, game.players[1].snake.direction);
+}
+
+#[test]
+fn second_player_should_start_mirrored(){
+    let frame = Frame{min_x:0,min_y:0,max_x:30,max_y:30};
+    let snake = Snake::spawn(Point{x:8,y:22}, Direction::Top).mirrored(&frame);
+    assert_eq!(Direction::Bottom, snake.direction);
+    assert_eq!(vec![Point{x:22,y:8}, Point{x:22,y:7}, Point{x:22,y:6}], snake.points.iter().cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn get_state_should_tag_snake_owners(){
+    let controller = GameController::with_seed(&Level::default(), 1).with_second_player();
+    let state = controller.get_state();
+    let heads: Vec<Option<usize>> = state.iter()
+        .filter(|s| s.state_type == PointDtoType::Head)
+        .map(|s| s.owner)
+        .collect();
+    assert_eq!(vec![Some(0), Some(1)], heads);
+    assert!(state.iter().filter(|s| s.state_type == PointDtoType::Frame).all(|s| s.owner.is_none()));
+    assert_eq!(vec![0, 0], controller.get_scores());
+}
+
+#[test]
+fn ai_should_head_to_food(){
+    let game = Game::new(20, 20, 1)
+        .with_snake(Snake::spawn(Point{x:5,y:10}, Direction::Right))
+        .with_foods(vec![Food{point: Point{x:5,y:15}, kind: FoodKind::Normal}]);
+    for level in [AiLevel::Greedy, AiLevel::Cautious, AiLevel::TailSafe].iter() {
+        assert_eq!(Some(Direction::Bottom), choose_direction(&game, 0, *level));
+    }
+}
+
+#[test]
+fn ai_should_not_turn_into_the_frame(){
+    let game = Game::new(20, 20, 1)
+        .with_snake(Snake::spawn(Point{x:19,y:10}, Direction::Right))
+        .with_foods(vec![Food{point: Point{x:10,y:10}, kind: FoodKind::Normal}]);
+    for level in [AiLevel::Greedy, AiLevel::Cautious, AiLevel::TailSafe].iter() {
+        let direction = choose_direction(&game, 0, *level);
+        assert!(direction == Some(Direction::Top) || direction == Some(Direction::Bottom));
+    }
+}
+
+#[test]
+fn safe_ai_should_survive_alone(){
+    for seed in 0..5 {
+        let mut game = Game::new(12, 12, seed).with_bot(0, Some(AiLevel::TailSafe));
+        for _ in 0..500 {
+            game = game.update(0.031);
+        }
+        match game.phase {
+            Phase::Playing | Phase::GameOver(GameSummary{outcome: Outcome::BoardCleared, ..}) => {}
+            phase => panic!("seed {} unexpected phase {:?}", seed, phase),
+        }
+        assert!(game.players[0].score > 10);
+    }
+}
+
+#[test]
+fn bots_should_ignore_human_turns(){
+    let controller = GameController::with_seed(&Level::default(), 1)
+        .with_bots(2, AiLevel::Cautious);
+    assert_eq!(3, controller.get_scores().len());
+    assert!(!controller.is_bot(0));
+    assert!(controller.is_bot(1) && controller.is_bot(2));
+    let game = controller.game.with_phase(Phase::Playing)
+        .handle_command(Command::Turn(1, Direction::Top));
+    assert!(game.players[1].turns.is_empty());
+    let heads: Vec<Point> = game.players.iter().map(|p| p.snake.head()).collect();
+    assert!(heads.iter().enumerate().all(|(i, h)| heads.iter().skip(i + 1).all(|o| o != h)));
+}
+
+#[test]
+fn hamiltonian_cycle_should_visit_every_cell_once(){
+    for (height, width, topology) in [(9, 9, Topology::Walled), (8, 9, Topology::Walled), (9, 6, Topology::Wrapped)].iter() {
+        let game = Game::new(*height, *width, 1).with_topology(*topology);
+        let cycle = HamiltonianCycle::new(&game).unwrap();
+        let (min_x, min_y, max_x, max_y) = game.food_generator.bounds().unwrap();
+        let mut order = vec![None; cycle.len()];
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let index = cycle.index(&Point{x, y});
+                assert_eq!(None, order[index]);
+                order[index] = Some(Point{x, y});
+            }
+        }
+        let order: Vec<Point> = order.into_iter().map(|p| p.unwrap()).collect();
+        for (i, p) in order.iter().enumerate() {
+            let next = &order[(i + 1) % order.len()];
+            let distance = (p.x as isize - next.x as isize).abs() + (p.y as isize - next.y as isize).abs();
+            assert_eq!(1, distance);
+        }
+    }
+    assert!(HamiltonianCycle::new(&Game::new(8, 8, 1)).is_none());
+}
+
+#[test]
+fn autopilot_should_clear_the_board(){
+    for seed in 0..3 {
+        let mut game = Game::new(9, 9, seed).handle_command(Command::Autopilot(0));
+        for _ in 0..20000 {
+            game = game.update(0.031);
+            if game.phase != Phase::Playing {
+                break;
+            }
+        }
+        match game.phase {
+            Phase::GameOver(summary) => {
+                assert_eq!(Outcome::BoardCleared, summary.outcome);
+                //Последняя еда удлиняет змейку в уже занятой клетке
+                assert_eq!(65, summary.players[0].length);
+                assert_eq!(64, game.grid.snake_cells());
+            }
+            phase => panic!("seed {} unexpected phase {:?}", seed, phase),
+        }
+    }
+}
+
+#[test]
+fn autopilot_should_clear_the_shipped_classic_level(){
+    let level = LevelRepository::load("../game/levels/classic.txt").unwrap();
+    assert_eq!(Level::default().frame(), level.frame());
+    let mut game = GameController::with_seed(&level, 1).game
+        .with_phase(Phase::Playing)
+        .handle_command(Command::Autopilot(0));
+    assert!(HamiltonianCycle::new(&game).is_some());
+    while game.phase == Phase::Playing && game.ticks < 1_000_000 {
+        game = game.advance();
+    }
+    match game.phase {
+        Phase::GameOver(summary) => assert_eq!(Outcome::BoardCleared, summary.outcome),
+        phase => panic!("unexpected phase {:?} after {} ticks", phase, game.ticks),
+    }
+    assert_eq!(900, game.grid.snake_cells());
+}
+
+#[test]
+fn autopilot_should_toggle(){
+    let controller = GameController::with_seed(&Level::default(), 1);
+    let controller = controller.update(0.0, vec![Command::Autopilot(0)]);
+    assert!(controller.is_autopilot(0));
+    assert!(controller.is_bot(0));
+    let controller = controller.update(0.0, vec![Command::Autopilot(0)]);
+    assert!(!controller.is_autopilot(0));
+}
+
+#[test]
+fn messages_should_survive_a_round_trip(){
+    let mut bytes = Vec::new();
+    let message = ClientMessage::Command(Command::Turn(1, Direction::Left));
+    send_message(&mut bytes, &message).unwrap();
+    let received: ClientMessage = receive_message(&mut bytes.as_slice()).unwrap();
+    assert_eq!(message, received);
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(receive_message::<ClientMessage, _>(&mut &truncated[..]).is_err());
+}
+
+//Шагаем сервером и обновляем клиентов пока условие не выполнится
+fn exchange(server: GameServer, clients: Vec<RemoteGame>, done: &dyn Fn(&GameServer, &[RemoteGame]) -> bool) -> (GameServer, Vec<RemoteGame>) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let (mut server, mut clients) = (server, clients);
+    while !done(&server, &clients) {
+        assert!(std::time::Instant::now() < deadline, "server and clients did not agree in time");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        server = server.tick(0.0);
+        clients = clients.into_iter().map(|c| c.update(Vec::new())).collect();
+    }
+    (server, clients)
+}
+
+#[test]
+fn server_should_play_with_clients_over_localhost(){
+    let controller = GameController::with_seed(&Level::default(), 1).with_players(2);
+    let server = GameServer::bind("127.0.0.1:0", controller).unwrap();
+    let address = server.local_addr().unwrap();
+    let first = RemoteGame::connect(address).unwrap();
+    let (server, mut clients) = exchange(server, vec![first], &|_, c| c[0].is_welcomed());
+    clients.push(RemoteGame::connect(address).unwrap());
+    let (server, mut clients) = exchange(server, clients, &|_, c| c[1].is_welcomed());
+    clients.push(RemoteGame::connect(address).unwrap());
+    let (server, clients) = exchange(server, clients, &|_, c| c[2].is_welcomed());
+    let players: Vec<Option<usize>> = clients.iter().map(|c| c.player()).collect();
+    assert_eq!(vec![Some(0), Some(1), None], players);
+
+    //Второй клиент пытается повернуть чужую змейку, но сервер повернет его собственную
+    let mut clients = clients;
+    let second = clients.remove(1).update(vec![Command::Confirm]);
+    clients.insert(1, second);
+    let (server, mut clients) = exchange(server, clients, &|s, _| s.controller.get_phase() != Phase::Title);
+    let server = server.tick(COUNTDOWN_SECONDS);
+    assert_eq!(Phase::Playing, server.controller.get_phase());
+    let second = clients.remove(1).update(vec![Command::Turn(0, Direction::Top)]);
+    clients.insert(1, second);
+    let (server, clients) = exchange(server, clients, &|s, _| !s.controller.game.players[1].turns.is_empty());
+    assert!(server.controller.game.players[0].turns.is_empty());
+    assert_eq!(Some(&Direction::Top), server.controller.game.players[1].turns.front());
+
+    let expected = server.controller.snapshot();
+    let (_, clients) = exchange(server, clients, &|_, c| c[2].snapshot().phase == expected.phase && c[2].snapshot().points == expected.points);
+    assert_eq!(2, clients[2].snapshot().scores.len());
+    assert!(clients.iter().all(|c| c.is_connected()));
+}
+
+#[test]
+fn server_should_ignore_commands_from_spectators(){
+    let controller = GameController::with_seed(&Level::default(), 1);
+    let server = GameServer::bind("127.0.0.1:0", controller).unwrap();
+    let address = server.local_addr().unwrap();
+    let (server, _player) = exchange(server, vec![RemoteGame::connect(address).unwrap()], &|_, c| c[0].is_welcomed());
+    let mut spectator = std::net::TcpStream::connect(address).unwrap();
+    let mut server = server;
+    let start = std::time::Instant::now();
+    while server.clients() < 2 && start.elapsed().as_secs() < 5 {
+        server = server.tick(0.0);
+    }
+    assert_eq!(ServerMessage::Welcome { player: None }, receive_message(&mut spectator).unwrap());
+    send_message(&mut spectator, &ClientMessage::Command(Command::Confirm)).unwrap();
+    let start = std::time::Instant::now();
+    while start.elapsed().as_millis() < 300 {
+        server = server.tick(0.0);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert_eq!(Phase::Title, server.controller.get_phase());
+    assert_eq!(0, server.encode_errors());
+}
+
+#[test]
+fn server_should_drop_a_client_that_stops_reading(){
+    let controller = GameController::with_seed(&Level::default(), 1);
+    let mut server = GameServer::bind("127.0.0.1:0", controller).unwrap();
+    //Клиент подключается и ничего не читает, его буферы скоро переполнятся
+    let _stalled = std::net::TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    let start = std::time::Instant::now();
+    while server.clients() == 0 && start.elapsed().as_secs() < 5 {
+        server = server.tick(0.0);
+    }
+    assert_eq!(1, server.clients());
+    while server.clients() > 0 && start.elapsed().as_secs() < 30 {
+        let tick = std::time::Instant::now();
+        server = server.tick(0.0);
+        assert!(tick.elapsed().as_millis() < 500, "server tick blocked for {:?}", tick.elapsed());
+    }
+    assert_eq!(0, server.clients());
+}
+
+//Играем с неровным временем кадров, паузой и автопилотом
+fn recorded_game() -> GameController {
+    let mut controller = GameController::with_seed(&Level::default(), 1)
+        .with_bots(1, AiLevel::Cautious)
+        .update(0.0, vec![Command::Confirm])
+        .update(COUNTDOWN_SECONDS, Vec::new());
+    for frame in 0..3000 {
+        let commands = match frame {
+            2 => vec![Command::Turn(0, Direction::Bottom)],
+            10 => vec![Command::Pause, Command::Step, Command::Turn(0, Direction::Left), Command::Step, Command::Pause],
+            15 => vec![Command::Autopilot(0)],
+            _ => Vec::new(),
+        };
+        controller = controller.update(0.013 + (frame % 7) as f32 * 0.011, commands);
+        if let Phase::GameOver(_) = controller.get_phase() {
+            break;
+        }
+    }
+    controller
+}
+
+fn same_game(expected: &Game, actual: &Game) {
+    assert_eq!(expected.ticks, actual.ticks);
+    assert_eq!(expected.foods, actual.foods);
+    for (e, a) in expected.players.iter().zip(actual.players.iter()) {
+        assert_eq!(e.snake.points, a.snake.points);
+        assert_eq!(e.score, a.score);
+        assert_eq!(e.death, a.death);
+    }
+}
+
+#[test]
+fn replay_should_repeat_the_recorded_game(){
+    let controller = recorded_game();
+    let replay = controller.get_replay().unwrap().clone();
+    assert_eq!(controller.game.ticks, replay.ticks);
+    assert!(!replay.inputs.is_empty());
+    let player = ReplayPlayer::new(replay.clone()).seek(replay.ticks);
+    assert!(player.is_finished());
+    same_game(&controller.game, &player.controller.game);
+    assert_eq!(controller.get_phase(), player.controller.get_phase());
+
+    //Повтор по времени кадров приходит к той же игре
+    let mut player = ReplayPlayer::new(replay.clone()).update(0.0, vec![PlaybackCommand::Faster]);
+    while !player.is_finished() {
+        player = player.update(0.05, Vec::new());
+    }
+    same_game(&controller.game, &player.controller.game);
+}
+
+#[test]
+fn replay_should_seek_backwards_and_survive_a_file(){
+    let replay = recorded_game().get_replay().unwrap().clone();
+    let path = std::env::temp_dir().join(format!("snake-{}.replay", std::process::id()));
+    let path = path.to_str().unwrap();
+    ReplayRepository::save(path, &replay).unwrap();
+    let loaded = ReplayRepository::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(replay, loaded);
+
+    let forward = ReplayPlayer::new(loaded.clone()).seek(20);
+    let back = ReplayPlayer::new(loaded).seek(replay.ticks).seek(20);
+    assert_eq!(20, back.tick());
+    same_game(&forward.controller.game, &back.controller.game);
+}
+
+#[test]
+fn saved_game_should_continue_the_same_way(){
+    let mut game = Game::new(12, 12, 5)
+        .with_food_config(FoodConfig::mixed(3))
+        .with_bot(0, Some(AiLevel::TailSafe));
+    for _ in 0..100 {
+        game = game.update(0.031);
+    }
+    let path = std::env::temp_dir().join(format!("snake-{}.save", std::process::id()));
+    let path = path.to_str().unwrap();
+    SaveRepository::save(path, &game, None).unwrap();
+    let (loaded, recording) = SaveRepository::load(path).unwrap();
+    assert_eq!(game.grid, loaded.grid);
+    assert_eq!(game.total_time, loaded.total_time);
+    assert!(recording.is_none());
+
+    let resumed = GameController::resumed(loaded, recording);
+    assert_eq!(Phase::Paused, resumed.get_phase());
+    let (mut game, mut loaded) = (game, resumed.game.with_phase(Phase::Playing));
+    for _ in 0..100 {
+        game = game.update(0.031);
+        loaded = loaded.update(0.031);
+    }
+    same_game(&game, &loaded);
+
+    std::fs::write(path, [99u8]).unwrap();
+    assert!(SaveRepository::load(path).is_err());
+    SaveRepository::remove(path).unwrap();
+    SaveRepository::remove(path).unwrap();
+}
+
+#[test]
+fn resumed_game_should_keep_its_recording(){
+    let mut controller = GameController::with_seed(&Level::default(), 3)
+        .with_bots(1, AiLevel::Cautious)
+        .update(0.0, vec![Command::Confirm])
+        .update(COUNTDOWN_SECONDS, vec![Command::Autopilot(0)]);
+    for _ in 0..50 {
+        controller = controller.update(0.031, Vec::new());
+    }
+    assert!(controller.is_in_progress());
+    let path = std::env::temp_dir().join(format!("snake-recording-{}.save", std::process::id()));
+    let path = path.to_str().unwrap();
+    SaveRepository::save(path, &controller.game, controller.get_replay()).unwrap();
+    let (game, recording) = SaveRepository::load(path).unwrap();
+    SaveRepository::remove(path).unwrap();
+
+    let mut resumed = GameController::resumed(game, recording).update(0.0, vec![Command::Pause]);
+    for _ in 0..300 {
+        resumed = resumed.update(0.031, Vec::new());
+        if let Phase::GameOver(_) = resumed.get_phase() {
+            break;
+        }
+    }
+    let replay = resumed.get_replay().unwrap().clone();
+    let player = ReplayPlayer::new(replay.clone()).seek(replay.ticks);
+    same_game(&resumed.game, &player.controller.game);
+    assert_eq!(resumed.get_phase(), player.controller.get_phase());
+
+    //Сохранение неизвестной версии не читается как поврежденное
+    std::fs::write(path, [99u8]).unwrap();
+    let error = SaveRepository::load(path).unwrap_err().to_string();
+    SaveRepository::remove(path).unwrap();
+    assert!(error.contains("unknown save version 99"), "{}", error);
+}
+
+#[test]
+fn simulation_should_report_the_same_games_for_the_same_seeds(){
+    let run = || (0..3u64)
+        .map(|seed| simulate(Game::new(12, 12, seed), &mut BuiltinPolicy(AiLevel::Greedy), 1.0 / 60.0, 2000))
+        .map(|game| GameReport::new(&game))
+        .collect::<Vec<GameReport>>();
+    let reports = run();
+    assert_eq!(reports, run());
+    assert!(reports.iter().all(|r| r.ticks > 0 && r.length >= 3));
+    let statistics = Statistics::new(&reports);
+    assert_eq!(3, statistics.games);
+    assert_eq!(3, statistics.causes.iter().map(|c| c.1).sum::<usize>());
+    assert!(statistics.min_score as f64 <= statistics.mean_score && statistics.mean_score <= statistics.max_score as f64);
+
+    let timeout = simulate(Game::new(12, 12, 1), &mut BuiltinPolicy(AiLevel::TailSafe), 1.0 / 60.0, 5);
+    assert_eq!(5, timeout.ticks);
+    assert_eq!("timeout", GameReport::new(&timeout).cause);
+}
+
+#[test]
+fn environment_should_observe_and_reward_steps(){
+    let rewards = RewardConfig { food: 1.0, death: -5.0, step: -0.01, approach: 0.1, win: 10.0 };
+    let (environment, observation) = Environment::new(Level::default())
+        .with_rewards(rewards)
+        .with_max_ticks(500)
+        .reset(7);
+    assert_eq!((32, 32), (observation.width, observation.height));
+    assert_eq!(CHANNELS * 32 * 32, observation.grid.len());
+    assert_eq!(FEATURES, observation.features.len());
+    let channel = |c: usize| observation.grid[c * 32 * 32..(c + 1) * 32 * 32].iter().sum::<f32>();
+    assert_eq!(1.0, channel(HEAD_CHANNEL));
+    assert_eq!(2.0, channel(BODY_CHANNEL));
+    assert_eq!(1.0, channel(FOOD_CHANNEL));
+    assert_eq!(4.0 * 31.0, channel(WALL_CHANNEL));
+    let head = environment.game.players[0].snake.head();
+    assert_eq!(1.0, observation.grid[(HEAD_CHANNEL * 32 + head.y as usize) * 32 + head.x as usize]);
+
+    //Ведем змейку к еде встроенным компьютером, награда должна совпасть со счетом
+    let (mut environment, mut total, mut steps) = (environment, 0.0, 0);
+    loop {
+        let action = choose_direction(&environment.game, 0, AiLevel::Greedy)
+            .unwrap_or(environment.game.players[0].snake.direction.clone());
+        let (next, step) = environment.step(action);
+        environment = next;
+        total += step.reward;
+        steps += 1;
+        if step.done {
+            assert!(step.info.truncated || step.info.death.is_some());
+            assert_eq!(step.info.ticks, steps);
+            assert!(step.info.score > 0);
+            break;
+        }
+    }
+    assert!(total > 0.0);
+
+    //Одинаковое зерно дает одинаковое наблюдение
+    let (_, first) = Environment::new(Level::default()).reset(3);
+    let (_, second) = Environment::new(Level::default()).reset(3);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn environment_should_be_ready_before_reset(){
+    let environment = Environment::new(Level::default()).with_bots(1, AiLevel::Greedy);
+    let (reset, observation) = environment.clone().reset(0);
+    assert_eq!(observation, environment.observe());
+    let (environment, step) = environment.step(Direction::Top);
+    let (_, expected) = reset.step(Direction::Top);
+    assert!(!step.done);
+    assert_eq!(expected, step);
+    assert_eq!(1, environment.game.ticks);
+}
+
+#[test]
+fn environment_should_not_advance_a_finished_game(){
+    //Едем вправо пока змейка не врежется в стену
+    let (mut environment, _) = Environment::new(Level::default()).reset(1);
+    let last = loop {
+        let (next, step) = environment.step(Direction::Right);
+        environment = next;
+        if step.done {
+            break step;
+        }
+    };
+    assert!(last.info.death.is_some());
+    let (environment, step) = environment.step(Direction::Left);
+    assert_eq!(0.0, step.reward);
+    assert!(step.done);
+    assert_eq!(last.observation, step.observation);
+    assert_eq!(last.info, step.info);
+    assert_eq!(last.info.ticks, environment.game.ticks);
+
+    //После лимита шагов тоже
+    let (environment, _) = Environment::new(Level::default()).with_max_ticks(3).reset(1);
+    let (environment, _) = environment.step(Direction::Top);
+    let (environment, _) = environment.step(Direction::Top);
+    let (environment, last) = environment.step(Direction::Top);
+    assert!(last.done && last.info.truncated);
+    let (environment, step) = environment.step(Direction::Top);
+    assert_eq!((0.0, true), (step.reward, step.done));
+    assert_eq!(last.info, step.info);
+    assert_eq!(3, environment.game.ticks);
+}
+
+#[test]
+fn phase_message_should_be_shared_by_front_ends(){
+    assert_eq!("SNAKE - PRESS ENTER TO START", phase_message(&Phase::Title));
+    assert_eq!("3", phase_message(&Phase::Countdown { remaining: 2.5 }));
+    assert_eq!("", phase_message(&Phase::Playing));
+    let summary = GameSummary { ticks: 42, outcome: Outcome::Died(DeathCause::Wall), players: Vec::new() };
+    assert_eq!("GAME OVER: HIT A WALL IN 42 TICKS - PRESS ENTER TO PLAY AGAIN", phase_message(&Phase::GameOver(summary)));
+    assert_eq!("PLAYER 2 WINS", outcome_message(&Outcome::Won(1)));
+}
+
+//Представление для тестов которое запоминает что ему дали нарисовать
+#[derive(Default)]
+struct RecordingRenderer {
+    phases: Vec<Phase>,
+}
+
+impl Renderer for RecordingRenderer {
+    fn render(&mut self, snapshot: &GameSnapshot, _: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.phases.push(snapshot.phase.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn run_loop_should_feed_scripted_commands_to_the_game(){
+    let controller = GameController::with_seed(&Level::default(), 1);
+    let mut renderer = RecordingRenderer::default();
+    let mut input = ScriptedInput::new(vec![Vec::new(), vec![Command::Confirm], vec![Command::Autopilot(0)]]);
+    let controller = run(controller, &mut renderer, &mut input).unwrap();
+    assert_eq!(3, renderer.phases.len());
+    assert_eq!(Phase::Title, renderer.phases[0]);
+    assert!(matches!(renderer.phases[1], Phase::Countdown { .. }));
+    assert!(controller.is_autopilot(0));
+    assert!(input.is_quit());
+}
+
+//Команды представления для тестов: команды игры и отметка о которой игра не знает
+#[derive(Debug, Clone, PartialEq)]
+enum TestAction {
+    Game(Command),
+    Mark,
+}
+
+//Источник игры который сам считает отметки, а команды игры передает контроллеру
+struct MarkedGame {
+    controller: GameController,
+    marks: usize,
+}
+
+impl GameSource<TestAction> for MarkedGame {
+    fn update(self, time_delta: f32, actions: Vec<TestAction>) -> MarkedGame {
+        let marks = self.marks + actions.iter().filter(|a| **a == TestAction::Mark).count();
+        let commands = actions.into_iter()
+            .filter_map(|a| match a {
+                TestAction::Game(command) => Some(command),
+                TestAction::Mark => None,
+            })
+            .collect();
+        MarkedGame { controller: self.controller.update(time_delta, commands), marks }
+    }
+
+    fn snapshot(&self) -> GameSnapshot {
+        self.controller.snapshot()
+    }
+}
+
+#[test]
+fn run_loop_should_accept_front_end_actions(){
+    let game = MarkedGame { controller: GameController::with_seed(&Level::default(), 1), marks: 0 };
+    let mut renderer = RecordingRenderer::default();
+    let mut input = ScriptedInput::new(vec![vec![TestAction::Mark], vec![TestAction::Game(Command::Confirm), TestAction::Mark]]);
+    let game = run(game, &mut renderer, &mut input).unwrap();
+    assert_eq!(2, game.marks);
+    assert!(matches!(game.controller.get_phase(), Phase::Countdown { .. }));
+}
+
+fn small_snapshot() -> GameSnapshot {
+    let point = |x, y, state_type| PointDto { x, y, state_type, owner: None };
+    GameSnapshot {
+        points: vec![
+            point(0, 0, PointDtoType::Wall),
+            point(2, 0, PointDtoType::Food),
+            point(1, 1, PointDtoType::Tail),
+            point(0, 1, PointDtoType::Head),
+        ],
+        scores: vec![3],
+        max_score: 7,
+        ..GameSnapshot::default()
+    }
+}
+
+#[test]
+fn snapshot_should_render_to_svg_and_png(){
+    let snapshot = small_snapshot();
+    assert_eq!("SCORE: 3  MAX: 7", score_line(&snapshot));
+    let short = GameSnapshot { scores: vec![1, 2, 3], bots: vec![false, true], ..snapshot.clone() };
+    assert_eq!("P1: 1  AI2: 2  P3: 3  MAX: 7", score_line(&short));
+    let autopilot = GameSnapshot { autopilots: vec![false, true], ..short.clone() };
+    assert_eq!(("AI2".to_string(), "P2".to_string()), (player_name(&short, 1), player_name(&autopilot, 1)));
+    assert_eq!(concat!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"12\" height=\"15\" viewBox=\"0 0 12 15\">\n",
+        "<rect width=\"12\" height=\"15\" fill=\"#101010\"/>\n",
+        "<text x=\"1\" y=\"6\" font-family=\"monospace\" font-size=\"5\" fill=\"#ffffff\">SCORE: 3  MAX: 7</text>\n",
+        "<rect x=\"0\" y=\"11\" width=\"4\" height=\"4\" fill=\"#a0a0a0\"/>\n",
+        "<circle cx=\"10\" cy=\"13\" r=\"2\" fill=\"#00ff00\"/>\n",
+        "<rect x=\"4\" y=\"7\" width=\"4\" height=\"4\" fill=\"#ffff00\"/>\n",
+        "<rect x=\"0\" y=\"7\" width=\"4\" height=\"4\" fill=\"#ff0000\"/>\n",
+        "</svg>\n"), snapshot_svg(&snapshot, 4, &Theme::default()));
+
+    let picture = Picture::from_snapshot(&snapshot, 4, &Theme::default());
+    assert_eq!((12, 15), (picture.width, picture.height));
+    assert_eq!([255, 0, 0], picture.pixel(1, 8));
+    assert_eq!([255, 255, 0], picture.pixel(5, 8));
+    assert_eq!([160, 160, 160], picture.pixel(1, 12));
+    assert_eq!([0, 255, 0], picture.pixel(9, 13));
+    assert_eq!([16, 16, 16], picture.pixel(8, 11));
+    //Первая буква S строки счета
+    assert_eq!([255, 255, 255], picture.pixel(1, 1));
+
+    let path = std::env::temp_dir().join(format!("snake-{}.png", std::process::id()));
+    let path = path.to_str().unwrap();
+    PictureRepository::save(path, &snapshot, 4, &Theme::default()).unwrap();
+    let loaded = PictureRepository::load_png(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(picture, loaded);
+    assert!(PictureRepository::save("snapshot.bmp", &snapshot, 4, &Theme::default()).is_err());
+    assert_eq!([156, 188, 88], Picture::from_snapshot(&snapshot, 4, &"retro".parse().unwrap()).pixel(8, 11));
+}
+
+#[test]
+fn replay_should_export_trimmed_animations(){
+    let replay = recorded_game().get_replay().unwrap().clone();
+    let config = AnimationConfig { fps: 20, cell: 2, from: 20, to: Some(60), ..AnimationConfig::default() };
+    let frames: Vec<Picture> = replay_frames(&replay, &config).unwrap().collect();
+    assert_eq!(frames.len(), replay_frames(&replay, &config).unwrap().count());
+    let start = ReplayPlayer::new(replay.clone()).seek(20).controller.snapshot();
+    assert_eq!(Picture::from_snapshot(&start, 2, &config.theme), frames[0]);
+    let end = ReplayPlayer::new(replay.clone()).seek(60).controller.snapshot();
+    assert_eq!(Some(&Picture::from_snapshot(&end, 2, &config.theme)), frames.last());
+    assert!(frames.len() > 2);
+
+    let path = std::env::temp_dir().join(format!("snake-{}.apng", std::process::id()));
+    let path = path.to_str().unwrap();
+    assert_eq!(frames.len(), AnimationRepository::save(path, &replay, &config).unwrap());
+    let mut reader = png::Decoder::new(std::fs::File::open(path).unwrap()).read_info().unwrap();
+    assert_eq!(Some(frames.len() as u32), reader.info().animation_control().map(|a| a.num_frames));
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    for _ in 0..frames.len() {
+        reader.next_frame(&mut buffer).unwrap();
+    }
+    assert!(reader.next_frame(&mut buffer).is_err());
+    std::fs::remove_file(path).unwrap();
+
+    let path = std::env::temp_dir().join(format!("snake-{}.gif", std::process::id()));
+    let path = path.to_str().unwrap();
+    AnimationRepository::save(path, &replay, &config).unwrap();
+    let mut decoder = gif::DecodeOptions::new().read_info(std::fs::File::open(path).unwrap()).unwrap();
+    let mut count = 0;
+    while decoder.read_next_frame().unwrap().is_some() {
+        count += 1;
+    }
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(frames.len(), count);
+
+    //Конец раньше начала это ошибка
+    let reversed = AnimationConfig { from: 60, to: Some(20), ..config.clone() };
+    assert!(replay_frames(&replay, &reversed).is_err());
+    assert!(AnimationRepository::save(path, &replay, &reversed).is_err());
+}
+
+#[test]
+fn gif_should_keep_the_frame_rate_when_100_is_not_divisible_by_fps(){
+    let frames = vec![Picture::new(2, 2, [0, 0, 0]); 15];
+    let path = std::env::temp_dir().join(format!("snake-{}-15fps.gif", std::process::id()));
+    let path = path.to_str().unwrap();
+    assert_eq!(15, AnimationRepository::save_gif(path, frames, 15).unwrap());
+    let mut decoder = gif::DecodeOptions::new().read_info(std::fs::File::open(path).unwrap()).unwrap();
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    std::fs::remove_file(path).unwrap();
+    //Пятнадцать кадров при 15 кадрах в секунду идут ровно секунду
+    assert_eq!(100, delays.iter().sum::<u16>());
+    assert!(delays.iter().all(|d| *d == 6 || *d == 7));
+}
+
+#[test]
+fn options_should_read_named_values(){
+    let args: Vec<String> = vec!["game", "--food", "3", "--wrap", "--level"].into_iter().map(String::from).collect();
+    assert_eq!(Ok(Some(3)), parse_option::<usize>(&args, "--food"));
+    assert_eq!(Ok(None), parse_option::<usize>(&args, "--bots"));
+    assert_eq!(None, option(&args, "--level"));
+    assert_eq!(Ok(7), parse(&args, "--bots", 7));
+
+    //Параметр без значения не забирает следующий параметр, а ошибка разбора возвращается программе
+    let args: Vec<String> = vec!["game", "--level", "--food", "x", "--fps", "10"].into_iter().map(String::from).collect();
+    assert_eq!(None, option(&args, "--level"));
+    assert!(parse_option::<usize>(&args, "--food").unwrap_err().contains("--food x"));
+    assert_eq!(Ok(10), parse(&args, "--fps", 25));
+}
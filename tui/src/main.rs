@@ -0,0 +1,235 @@
+/*Игра в терминале. Поле рисуется символами Unicode и цветами ANSI,
+стрелки читаются из терминала в сыром режиме. Работает по SSH и без OpenGL.
+Логику берет из того же контроллера что и 3D версия.
+*/
+extern crate snake_core;
+extern crate crossterm;
+
+use std::error::Error;
+use std::io::{Stdout, Write};
+use std::time::Duration;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use snake_core::{option, parse, parse_option, phase_message, run, score_line, AiLevel, Command, Difficulty, Direction, FoodConfig, GameController, GameSnapshot, InputSource, LevelRepository, PointDto, PointDtoType, Renderer, Rgb, ScoreRepository, Theme, Topology};
+
+//Папка с уровнями игры
+const LEVELS_DIRECTORY: &str = "./levels";
+
+//Сколько ждем нажатия клавиши прежде чем нарисовать следующий кадр
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+//Клетка поля занимает два символа чтобы поле не было вытянутым по вертикали
+const BLOCK: &str = "██";
+const FOOD: &str = "●●";
+const EMPTY: &str = "  ";
+const DOT: &str = "··";
+
+//Переводит терминал в сырой режим и возвращает его обратно при выходе, даже если игра упала
+struct Terminal {
+    stdout: Stdout,
+}
+
+impl Terminal {
+    fn new() -> std::io::Result<Terminal> {
+        let mut stdout = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+        Ok(Terminal { stdout })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+//Клавиатура терминала
+struct TerminalInput {
+    quit: bool,
+}
+
+impl InputSource for TerminalInput {
+    //Читаем все нажатия с прошлого кадра. Управление такое же как в 3D версии:
+    //первый игрок стрелками, второй WASD
+    fn commands(&mut self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let mut timeout = FRAME_TIME;
+        while let Ok(true) = event::poll(timeout) {
+            timeout = Duration::from_millis(0);
+            let key = match event::read() {
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+                _ => continue,
+            };
+            let command = match key.code {
+                KeyCode::Left => Some(Command::Turn(0, Direction::Left)),
+                KeyCode::Right => Some(Command::Turn(0, Direction::Right)),
+                KeyCode::Down => Some(Command::Turn(0, Direction::Top)),
+                KeyCode::Up => Some(Command::Turn(0, Direction::Bottom)),
+                KeyCode::Char('a') => Some(Command::Turn(1, Direction::Left)),
+                KeyCode::Char('d') => Some(Command::Turn(1, Direction::Right)),
+                KeyCode::Char('s') => Some(Command::Turn(1, Direction::Top)),
+                KeyCode::Char('w') => Some(Command::Turn(1, Direction::Bottom)),
+                KeyCode::Enter | KeyCode::Char(' ') => Some(Command::Confirm),
+                KeyCode::Char('p') => Some(Command::Pause),
+                KeyCode::Char('n') => Some(Command::Step),
+                KeyCode::Char('h') => Some(Command::Autopilot(0)),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.quit = true;
+                    None
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.quit = true;
+                    None
+                }
+                _ => None,
+            };
+            commands.extend(command);
+        }
+        commands
+    }
+
+    fn is_quit(&self) -> bool {
+        self.quit
+    }
+}
+
+//Представление игры в терминале
+struct TerminalRenderer {
+    terminal: Terminal,
+    //Цвета змеек остальных игроков и компьютера: голова и хвост, как на картинках
+    others: Vec<(Color, Color)>,
+}
+
+impl TerminalRenderer {
+    fn new(terminal: Terminal) -> TerminalRenderer {
+        let color = |c: Rgb| Color::Rgb { r: c[0], g: c[1], b: c[2] };
+        let others = Theme::default().others.into_iter().map(|(head, tail)| (color(head), color(tail))).collect();
+        TerminalRenderer { terminal, others }
+    }
+
+    //Символ и цвет точки игры
+    fn get_cell(&self, point: &PointDto, wrapped: bool) -> (&'static str, Color) {
+        let owner = point.owner.unwrap_or(0);
+        match point.state_type {
+            PointDtoType::Frame if wrapped => (DOT, Color::DarkGrey),
+            PointDtoType::Frame => (BLOCK, Color::Blue),
+            PointDtoType::Wall => (BLOCK, Color::Grey),
+            PointDtoType::Head if owner > 0 => (BLOCK, self.others[(owner - 1) % self.others.len()].0),
+            PointDtoType::Tail if owner > 0 => (BLOCK, self.others[(owner - 1) % self.others.len()].1),
+            PointDtoType::Head => (BLOCK, Color::Red),
+            PointDtoType::Tail => (BLOCK, Color::Yellow),
+            PointDtoType::Food => (FOOD, Color::Green),
+            PointDtoType::BonusFood => (FOOD, Color::Cyan),
+            PointDtoType::PoisonFood => (FOOD, Color::DarkMagenta),
+            PointDtoType::GoldenFood => (FOOD, Color::Rgb { r: 255, g: 215, b: 0 }),
+        }
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    //Рисуем кадр целиком. Сначала собираем его в памяти чтобы терминал не мерцал
+    fn render(&mut self, snapshot: &GameSnapshot, status: &str) -> Result<(), Box<dyn Error>> {
+        let wrapped = snapshot.topology == Topology::Wrapped;
+        let max_x = snapshot.points.iter().map(|p| p.x as usize).max().unwrap_or(0);
+        let max_y = snapshot.points.iter().map(|p| p.y as usize).max().unwrap_or(0);
+        let mut cells: Vec<Vec<(&str, Color)>> = vec![vec![(EMPTY, Color::Reset); max_x + 1]; max_y + 1];
+        //Змейки рисуем поверх еды, а их головы поверх хвостов
+        let order = |p: &&PointDto| match p.state_type {
+            PointDtoType::Head => 2,
+            PointDtoType::Tail => 1,
+            _ => 0,
+        };
+        let mut points: Vec<&PointDto> = snapshot.points.iter().collect();
+        points.sort_by_key(order);
+        for point in points {
+            cells[point.y as usize][point.x as usize] = self.get_cell(point, wrapped);
+        }
+        let autopilot = if snapshot.autopilots.first().cloned().unwrap_or(false) { "  AUTOPILOT (H - TAKE CONTROL)" } else { "" };
+        let status = if status.is_empty() { String::new() } else { format!("  {}", status) };
+        let message = phase_message(&snapshot.phase);
+        let out = &mut self.terminal.stdout;
+        queue!(out, MoveTo(0, 0), ResetColor)?;
+        queue!(out, Print(format!("{}{}{}", score_line(snapshot), autopilot, status)), Clear(ClearType::UntilNewLine))?;
+        //Первая строка поля это самый большой y, как в файлах уровней
+        for (row, line) in cells.iter().rev().enumerate() {
+            queue!(out, MoveTo(0, row as u16 + 1))?;
+            let mut color = Color::Reset;
+            for (text, cell_color) in line.iter() {
+                if *cell_color != color {
+                    queue!(out, SetForegroundColor(*cell_color))?;
+                    color = *cell_color;
+                }
+                queue!(out, Print(text))?;
+            }
+            queue!(out, ResetColor, Clear(ClearType::UntilNewLine))?;
+        }
+        queue!(out, MoveTo(0, cells.len() as u16 + 1), Print(message), Clear(ClearType::UntilNewLine))?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+//Неверный параметр командной строки завершает программу с понятной ошибкой
+fn or_exit<T>(value: Result<T, String>) -> T {
+    value.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let level = match option(&args, "--level") {
+        None => LevelRepository::load(&format!("{}/classic.txt", LEVELS_DIRECTORY)).unwrap_or_default(),
+        Some(name) => LevelRepository::load(&format!("{}/{}.txt", LEVELS_DIRECTORY, name)).unwrap_or_else(|e| {
+            eprintln!("Can not load level: {}", e);
+            std::process::exit(1);
+        }),
+    };
+    let topology = if args.iter().any(|a| a == "--wrap") {
+        Topology::Wrapped
+    } else {
+        Topology::Walled
+    };
+    let food = or_exit(parse_option::<usize>(&args, "--food"))
+        .map_or(FoodConfig::default(), FoodConfig::mixed);
+    let difficulty: Difficulty = or_exit(parse(&args, "--difficulty", Difficulty::default()));
+    //Второй игрок играет на той же клавиатуре, поэтому игроков не больше двух
+    let players: usize = or_exit(parse(&args, "--players", 1));
+    if !(1..=2).contains(&players) {
+        eprintln!("Unknown number of players '{}', expected 1 or 2", players);
+        std::process::exit(1);
+    }
+    let bots: usize = or_exit(parse(&args, "--bots", 0));
+    let ai: AiLevel = or_exit(parse(&args, "--ai", AiLevel::default()));
+    let controller = GameController::new(&level)
+        .with_topology(topology)
+        .with_food_config(food)
+        .with_speed(difficulty.speed_curve())
+        .with_max_score(ScoreRepository::load().unwrap_or(0))
+        .with_players(players)
+        .with_bots(bots, ai);
+    let controller = if args.iter().any(|a| a == "--autopilot") {
+        controller.update(0.0, vec![Command::Autopilot(0)])
+    } else {
+        controller
+    };
+    let result = Terminal::new()
+        .map_err(|e| Box::new(e) as Box<dyn Error>)
+        .and_then(|terminal| run(controller, &mut TerminalRenderer::new(terminal), &mut TerminalInput { quit: false }));
+    let controller = result.unwrap_or_else(|e| {
+        eprintln!("Terminal error: {}", e);
+        std::process::exit(1);
+    });
+    //Рекорд сохраняем когда игрок выходит, даже если игра не закончена
+    if controller.get_max_score() > ScoreRepository::load().unwrap_or(0) {
+        if let Err(e) = ScoreRepository::save(controller.get_max_score()) {
+            eprintln!("Can not save the max score: {}", e);
+        }
+    }
+}